@@ -0,0 +1,88 @@
+//! Password generation throughput for each random source
+//!
+//! Run with `cargo bench --bench rng`, optionally followed by `-- <count>`.
+//! The unbuffered source opens /dev/urandom for every request, as
+//! `SecureRandom` once did. `SecureRandom` now shares a ChaCha20 DRBG
+//! behind a lock, and an owned `ChaChaRng` skips the lock.
+
+use genpass::generator::{PasswordConstraints, PasswordGenerator};
+use genpass::random::{ChaChaRng, RandomSource, SecureRandom};
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+/// One open and one read per request
+struct Unbuffered;
+
+impl RandomSource for Unbuffered {
+    fn fill_bytes(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        File::open("/dev/urandom")?.read_exact(buf)
+    }
+}
+
+/// 64 characters from every ASCII class
+fn constraints() -> PasswordConstraints {
+    PasswordConstraints {
+        min_numeric: Some(1),
+        max_numeric: None,
+        min_lower: Some(1),
+        max_lower: None,
+        min_upper: Some(1),
+        max_upper: None,
+        min_symbol: Some(1),
+        max_symbol: None,
+        min_hiragana: None,
+        max_hiragana: None,
+        min_katakana: None,
+        max_katakana: None,
+        min_length: 64,
+        max_length: 64,
+        max_bytes: None,
+        symbols: "!@#$%^&*()_+-=[]{}|;:,.<>?".to_string(),
+        exclude_ambiguous: false,
+    }
+}
+
+/// Time `count` passwords and print the rate
+fn run(
+    name: &str,
+    generator: &PasswordGenerator,
+    rng: &mut dyn RandomSource,
+    count: usize,
+) -> Duration {
+    let start = Instant::now();
+    for _ in 0..count {
+        black_box(generator.generate_with(rng).expect("generation failed"));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<12} {:>9} passwords in {:>10.2?} ({:.2} us each)",
+        name,
+        count,
+        elapsed,
+        elapsed.as_secs_f64() * 1e6 / count as f64
+    );
+    elapsed
+}
+
+fn main() {
+    // cargo passes --bench, so take the first number
+    let count = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(10_000);
+
+    let generator = PasswordGenerator::new(constraints()).expect("valid constraints");
+
+    let unbuffered = run("unbuffered", &generator, &mut Unbuffered, count);
+    let shared = run("shared", &generator, &mut SecureRandom, count);
+    let mut drbg = ChaChaRng::new().expect("OS randomness");
+    let owned = run("owned", &generator, &mut drbg, count);
+    println!(
+        "speedup: {:.1}x shared, {:.1}x owned",
+        unbuffered.as_secs_f64() / shared.as_secs_f64(),
+        unbuffered.as_secs_f64() / owned.as_secs_f64()
+    );
+}
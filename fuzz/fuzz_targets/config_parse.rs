@@ -0,0 +1,16 @@
+#![no_main]
+
+//! Config files of any content parse without panicking, strict parsing
+//! fails exactly when lenient parsing reports a problem, and the
+//! constraints built from them validate without panicking either.
+
+use genpass::config::Config;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|content: &str| {
+    let (config, diagnostics) = Config::parse_lenient(content);
+    assert_eq!(Config::parse(content).is_ok(), diagnostics.is_empty());
+
+    let symbols = config.symbols.clone().unwrap_or_default();
+    let _ = config.password_constraints(symbols).validate();
+});
@@ -0,0 +1,45 @@
+#![no_main]
+
+//! Command lines go through the same steps as `main`: clap, the config
+//! merge and constraint construction. Whatever passes validation must
+//! build a generator and produce a compliant password.
+
+use clap::Parser;
+use genpass::cli::Cli;
+use genpass::config::Config;
+use genpass::generator::PasswordGenerator;
+use genpass::random::SeededRandom;
+use libfuzzer_sys::fuzz_target;
+
+/// Longest password worth generating per run; validation alone covers
+/// longer ones
+const MAX_GENERATED_LENGTH: usize = 64;
+
+fuzz_target!(|args: Vec<String>| {
+    let Ok(cli) = Cli::try_parse_from(std::iter::once("genpass".to_string()).chain(args)) else {
+        return;
+    };
+
+    let mut config = Config::default();
+    config.merge_with_cli(&cli);
+    let symbols = config.symbols.clone().unwrap_or_default();
+    let constraints = config.password_constraints(symbols);
+    if constraints.validate().is_err() || constraints.max_length > MAX_GENERATED_LENGTH {
+        return;
+    }
+
+    let generator = if config.uniform.unwrap_or(false) {
+        PasswordGenerator::uniform(constraints.clone())
+    } else {
+        PasswordGenerator::new(constraints.clone())
+    };
+    let generator = generator.expect("validated constraints build a generator");
+    let password = generator
+        .generate_with(&mut SeededRandom::new(0))
+        .expect("validated constraints generate");
+
+    // Symbols that are also letters or digits count as those
+    if constraints.symbols.chars().all(|c| !c.is_alphanumeric()) {
+        constraints.verify(&password).unwrap();
+    }
+});
@@ -0,0 +1,73 @@
+#![no_main]
+
+//! Arbitrary constraint sets either fail validation or generate a
+//! compliant password, in both sampling modes.
+
+use arbitrary::Arbitrary;
+use genpass::generator::{PasswordConstraints, PasswordGenerator};
+use genpass::random::SeededRandom;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    /// Minimum and maximum per class: numeric, lower, upper, symbol,
+    /// hiragana and katakana
+    limits: [(Option<u8>, Option<u8>); 6],
+    min_length: u8,
+    max_length: u8,
+    max_bytes: Option<u16>,
+    symbols: String,
+    exclude_ambiguous: bool,
+    uniform: bool,
+    seed: u64,
+}
+
+fuzz_target!(|input: Input| {
+    let limit = |value: Option<u8>| value.map(usize::from);
+    let [numeric, lower, upper, symbol, hiragana, katakana] = input.limits;
+
+    // Symbols that are also letters or digits count as those, which the
+    // generator's own per-class counts don't
+    let symbols: String = input
+        .symbols
+        .chars()
+        .filter(|c| !c.is_alphanumeric())
+        .collect();
+
+    let constraints = PasswordConstraints {
+        min_numeric: limit(numeric.0),
+        max_numeric: limit(numeric.1),
+        min_lower: limit(lower.0),
+        max_lower: limit(lower.1),
+        min_upper: limit(upper.0),
+        max_upper: limit(upper.1),
+        min_symbol: limit(symbol.0),
+        max_symbol: limit(symbol.1),
+        min_hiragana: limit(hiragana.0),
+        max_hiragana: limit(hiragana.1),
+        min_katakana: limit(katakana.0),
+        max_katakana: limit(katakana.1),
+        min_length: usize::from(input.min_length),
+        max_length: usize::from(input.max_length),
+        max_bytes: input.max_bytes.map(usize::from),
+        symbols,
+        exclude_ambiguous: input.exclude_ambiguous,
+    };
+
+    let valid = constraints.validate().is_ok();
+    let generator = if input.uniform {
+        PasswordGenerator::uniform(constraints.clone())
+    } else {
+        PasswordGenerator::new(constraints.clone())
+    };
+    let Ok(generator) = generator else {
+        assert!(!valid, "validated constraints were refused");
+        return;
+    };
+    assert!(valid, "a generator was built from invalid constraints");
+
+    let password = generator
+        .generate_with(&mut SeededRandom::new(input.seed))
+        .expect("validated constraints generate");
+    constraints.verify(&password).unwrap();
+});
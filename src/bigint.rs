@@ -0,0 +1,433 @@
+use crate::random::RandomSource;
+use std::cmp::Ordering;
+use std::fmt;
+use std::io;
+use std::ops::{Add, Mul, Sub};
+
+/// An arbitrary-precision unsigned integer
+///
+/// Only what keyspace counting and sampling need. Limbs are stored little-endian in
+/// base 2^32 with no trailing zero limbs, so zero is an empty vector.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    /// Zero
+    pub fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    /// One
+    pub fn one() -> Self {
+        Self::from(1u64)
+    }
+
+    /// Whether the value is zero
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Multiply by a small factor
+    pub fn mul_small(&self, factor: u32) -> Self {
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = 0u64;
+        for &limb in &self.limbs {
+            let product = limb as u64 * factor as u64 + carry;
+            limbs.push(product as u32);
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+
+        let mut result = Self { limbs };
+        result.normalize();
+        result
+    }
+
+    /// Divide by a small divisor, returning the quotient and remainder
+    ///
+    /// # Panics
+    /// Panics if the divisor is zero
+    pub fn div_rem_small(&self, divisor: u32) -> (Self, u32) {
+        assert!(divisor != 0, "division by zero");
+
+        let mut limbs = vec![0u32; self.limbs.len()];
+        let mut remainder = 0u64;
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            let value = (remainder << 32) | limb as u64;
+            limbs[i] = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+
+        let mut quotient = Self { limbs };
+        quotient.normalize();
+        (quotient, remainder as u32)
+    }
+
+    /// Raise to a power
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut result = Self::one();
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            base = &base * &base;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// The binomial coefficient `n` choose `k`
+    pub fn binomial(n: usize, k: usize) -> Self {
+        if k > n {
+            return Self::zero();
+        }
+
+        // Each partial product is itself a binomial, so the division is exact
+        let k = k.min(n - k);
+        let mut result = Self::one();
+        for i in 0..k {
+            result = result
+                .mul_small((n - i) as u32)
+                .div_rem_small(i as u32 + 1)
+                .0;
+        }
+        result
+    }
+
+    /// Number of significant bits
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(&top) => 32 * self.limbs.len() as u64 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// A uniformly random value in `0..bound`
+    ///
+    /// Draws just enough random bits and rejects values at or above the
+    /// bound, so each attempt succeeds with probability above one half.
+    ///
+    /// # Panics
+    /// Panics if the bound is zero
+    pub fn random_below(bound: &Self, rng: &mut dyn RandomSource) -> io::Result<Self> {
+        assert!(!bound.is_zero(), "empty range");
+
+        let bits = bound.bits();
+        let limbs = bits.div_ceil(32) as usize;
+        let top_mask = match bits % 32 {
+            0 => u32::MAX,
+            used => (1u32 << used) - 1,
+        };
+
+        let mut bytes = vec![0u8; limbs * 4];
+        loop {
+            rng.fill_bytes(&mut bytes)?;
+            let mut candidate = Self {
+                limbs: bytes
+                    .chunks_exact(4)
+                    .map(|chunk| u32::from_le_bytes(chunk.try_into().expect("4-byte chunk")))
+                    .collect(),
+            };
+            candidate.limbs[limbs - 1] &= top_mask;
+            candidate.normalize();
+
+            if candidate < *bound {
+                return Ok(candidate);
+            }
+        }
+    }
+
+    /// Pick an index with probability proportional to its weight
+    ///
+    /// # Panics
+    /// Panics if all weights are zero
+    pub fn random_weighted(weights: &[Self], rng: &mut dyn RandomSource) -> io::Result<usize> {
+        let total = weights.iter().fold(Self::zero(), |total, w| &total + w);
+        let mut r = Self::random_below(&total, rng)?;
+        for (i, weight) in weights.iter().enumerate() {
+            if r < *weight {
+                return Ok(i);
+            }
+            r = &r - weight;
+        }
+        unreachable!("random value below the total weight")
+    }
+
+    /// Base-2 logarithm, or negative infinity for zero
+    pub fn log2(&self) -> f64 {
+        let Some(&top) = self.limbs.last() else {
+            return f64::NEG_INFINITY;
+        };
+
+        // The top 64 bits carry all the precision an f64 can hold
+        let mut leading = top as f64;
+        if self.limbs.len() >= 2 {
+            leading = leading * 4294967296.0 + self.limbs[self.limbs.len() - 2] as f64;
+            leading.log2() + 32.0 * (self.limbs.len() - 2) as f64
+        } else {
+            leading.log2()
+        }
+    }
+
+    /// Drop trailing zero limbs
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0u64;
+        for i in 0..len {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64
+                + *other.limbs.get(i).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+
+        let mut result = BigUint { limbs };
+        result.normalize();
+        result
+    }
+}
+
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    /// # Panics
+    /// Panics if `other` is larger than `self`
+    fn sub(self, other: &BigUint) -> BigUint {
+        assert!(*self >= *other, "subtraction underflow");
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+        for i in 0..self.limbs.len() {
+            let mut diff = self.limbs[i] as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if diff < 0 {
+                diff += 1 << 32;
+                borrow = 1;
+            }
+            limbs.push(diff as u32);
+        }
+
+        let mut result = BigUint { limbs };
+        result.normalize();
+        result
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+
+        let mut result = BigUint { limbs };
+        result.normalize();
+        result
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Normalized values with more limbs are larger
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        let mut result = Self {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        };
+        result.normalize();
+        result
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+
+        // Peel off nine decimal digits at a time
+        let mut chunks = Vec::new();
+        let mut value = self.clone();
+        while !value.is_zero() {
+            let (quotient, remainder) = value.div_rem_small(1_000_000_000);
+            chunks.push(remainder);
+            value = quotient;
+        }
+
+        let mut digits = chunks.pop().unwrap_or_default().to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:09}", chunk));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::SecureRandom;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(BigUint::from(42).to_string(), "42");
+        assert_eq!(BigUint::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(BigUint::from(1_000_000_000).to_string(), "1000000000");
+    }
+
+    #[test]
+    fn test_mul_small() {
+        // 10^30 overflows u64
+        let mut value = BigUint::one();
+        for _ in 0..30 {
+            value = value.mul_small(10);
+        }
+        assert_eq!(value.to_string(), format!("1{}", "0".repeat(30)));
+        assert!(value.mul_small(0).is_zero());
+    }
+
+    #[test]
+    fn test_div_rem_small() {
+        let value = BigUint::from(u64::MAX).mul_small(7);
+        let (quotient, remainder) = value.div_rem_small(7);
+        assert_eq!(quotient, BigUint::from(u64::MAX));
+        assert_eq!(remainder, 0);
+
+        let (quotient, remainder) = BigUint::from(100).div_rem_small(7);
+        assert_eq!(quotient, BigUint::from(14));
+        assert_eq!(remainder, 2);
+    }
+
+    #[test]
+    fn test_add_sub_mul() {
+        let a = BigUint::from(u64::MAX);
+        let b = BigUint::from(12345);
+
+        let sum = &a + &b;
+        assert_eq!(sum.to_string(), "18446744073709563960");
+        assert_eq!(&sum - &b, a);
+        assert_eq!(&sum - &sum, BigUint::zero());
+
+        let product = &a * &a;
+        assert_eq!(
+            product.to_string(),
+            "340282366920938463426481119284349108225"
+        );
+        assert_eq!(&product * &BigUint::zero(), BigUint::zero());
+    }
+
+    #[test]
+    fn test_pow_and_ordering() {
+        assert_eq!(BigUint::from(2).pow(100).bits(), 101);
+        assert_eq!(BigUint::from(95).pow(0), BigUint::one());
+        assert_eq!(
+            BigUint::from(10).pow(20).to_string(),
+            format!("1{}", "0".repeat(20))
+        );
+
+        assert!(BigUint::from(2).pow(64) > BigUint::from(u64::MAX));
+        assert!(BigUint::from(3) < BigUint::from(4));
+        assert_eq!(BigUint::zero().bits(), 0);
+    }
+
+    #[test]
+    fn test_random_below() {
+        let bound = BigUint::from(3);
+        let mut seen = [false; 3];
+        for _ in 0..200 {
+            let value = BigUint::random_below(&bound, &mut SecureRandom).unwrap();
+            assert!(value < bound);
+            seen[value.to_string().parse::<usize>().unwrap()] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+
+        let bound = BigUint::from(2).pow(200);
+        for _ in 0..20 {
+            assert!(BigUint::random_below(&bound, &mut SecureRandom).unwrap() < bound);
+        }
+    }
+
+    #[test]
+    fn test_binomial() {
+        assert_eq!(BigUint::binomial(5, 2), BigUint::from(10));
+        assert_eq!(BigUint::binomial(5, 0), BigUint::one());
+        assert_eq!(BigUint::binomial(2, 5), BigUint::zero());
+        assert_eq!(
+            BigUint::binomial(100, 50).to_string(),
+            "100891344545564193334812497256"
+        );
+    }
+
+    #[test]
+    fn test_random_weighted() {
+        let weights = [
+            BigUint::zero(),
+            BigUint::from(1),
+            BigUint::zero(),
+            BigUint::from(3),
+        ];
+        let mut counts = [0; 4];
+        for _ in 0..400 {
+            counts[BigUint::random_weighted(&weights, &mut SecureRandom).unwrap()] += 1;
+        }
+        assert_eq!(counts[0] + counts[2], 0);
+        assert!(counts[3] > counts[1]);
+    }
+
+    #[test]
+    fn test_log2() {
+        assert_eq!(BigUint::from(1).log2(), 0.0);
+        assert_eq!(BigUint::from(1024).log2(), 10.0);
+
+        let mut value = BigUint::one();
+        for _ in 0..100 {
+            value = value.mul_small(95);
+        }
+        assert!((value.log2() - 100.0 * 95f64.log2()).abs() < 1e-9);
+        assert_eq!(BigUint::zero().log2(), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::random::{RandomSource, SecureRandom};
+use crate::sha256::Sha256;
+use std::io;
+use std::sync::OnceLock;
+
+/// The BIP39 English wordlist (2,048 words)
+///
+/// Source: https://github.com/bitcoin/bips/blob/master/bip-0039/english.txt
+const ENGLISH_WORDLIST: &str = include_str!("wordlists/bip39_english.txt");
+
+/// Mnemonic lengths allowed by BIP39
+pub const VALID_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// The English wordlist, split into words on first use
+fn english() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| ENGLISH_WORDLIST.lines().collect())
+}
+
+/// Encode entropy as a BIP39 mnemonic
+///
+/// The entropy must be 16, 20, 24, 28 or 32 bytes, giving 12 to 24 words.
+pub fn entropy_to_mnemonic(entropy: &[u8]) -> Result<String, String> {
+    if !(16..=32).contains(&entropy.len()) || !entropy.len().is_multiple_of(4) {
+        return Err(format!(
+            "BIP39 entropy must be 16, 20, 24, 28 or 32 bytes, got {}",
+            entropy.len()
+        ));
+    }
+
+    // ENT bits of entropy followed by ENT/32 bits of SHA-256 checksum
+    let checksum_bits = entropy.len() / 4;
+    let checksum = Sha256::digest(entropy);
+    let mut bits: Vec<bool> = Vec::with_capacity(entropy.len() * 8 + checksum_bits);
+    for byte in entropy.iter() {
+        bits.extend((0..8).rev().map(|i| (byte >> i) & 1 == 1));
+    }
+    bits.extend((0..checksum_bits).map(|i| (checksum[i / 8] >> (7 - i % 8)) & 1 == 1));
+
+    let words = english();
+    let mnemonic: Vec<&str> = bits
+        .chunks(11)
+        .map(|chunk| {
+            let index = chunk
+                .iter()
+                .fold(0usize, |acc, &bit| (acc << 1) | bit as usize);
+            words[index]
+        })
+        .collect();
+
+    Ok(mnemonic.join(" "))
+}
+
+/// Check a mnemonic's words and checksum, returning the encoded entropy
+///
+/// # Errors
+/// Returns an error describing the first problem found: a bad word
+/// count, a word not in the English list, or a checksum mismatch
+pub fn mnemonic_to_entropy(mnemonic: &str) -> Result<Vec<u8>, String> {
+    let words = english();
+    let input: Vec<&str> = mnemonic.split_whitespace().collect();
+
+    if !VALID_WORD_COUNTS.contains(&input.len()) {
+        return Err(format!(
+            "mnemonic has {} words; BIP39 uses 12, 15, 18, 21 or 24",
+            input.len()
+        ));
+    }
+
+    let mut bits: Vec<bool> = Vec::with_capacity(input.len() * 11);
+    for (position, word) in input.iter().enumerate() {
+        // The list is sorted, so a binary search finds the index
+        let index = words.binary_search(word).map_err(|_| {
+            format!(
+                "word {} ('{}') is not in the BIP39 English wordlist",
+                position + 1,
+                word
+            )
+        })?;
+        bits.extend((0..11).rev().map(|i| (index >> i) & 1 == 1));
+    }
+
+    let checksum_bits = input.len() / 3;
+    let entropy_bits = bits.len() - checksum_bits;
+    let entropy: Vec<u8> = bits[..entropy_bits]
+        .chunks(8)
+        .map(|chunk| chunk.iter().fold(0u8, |acc, &bit| (acc << 1) | bit as u8))
+        .collect();
+
+    let checksum = Sha256::digest(&entropy);
+    let expected = (0..checksum_bits).map(|i| (checksum[i / 8] >> (7 - i % 8)) & 1 == 1);
+    if !expected.eq(bits[entropy_bits..].iter().copied()) {
+        return Err("checksum mismatch".to_string());
+    }
+
+    Ok(entropy)
+}
+
+/// Generate a random BIP39 mnemonic with the given number of words
+pub fn generate(words: usize) -> io::Result<String> {
+    generate_with(words, &mut SecureRandom)
+}
+
+/// Generate a BIP39 mnemonic using the given random source
+pub fn generate_with(words: usize, rng: &mut dyn RandomSource) -> io::Result<String> {
+    if !VALID_WORD_COUNTS.contains(&words) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "BIP39 mnemonics have 12, 15, 18, 21 or 24 words",
+        ));
+    }
+
+    // 11 bits per word, one of every 33 bits is checksum
+    let mut entropy = vec![0u8; words * 4 / 3];
+    rng.fill_bytes(&mut entropy)?;
+
+    entropy_to_mnemonic(&entropy).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Test vectors from the reference implementation (trezor/python-mnemonic)
+    const VECTORS: &[(&str, &str)] = &[
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+        ),
+        (
+            "000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon agent",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal will",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+        ),
+        (
+            "9e885d952ad362caeb4efe34a8e91bd2",
+            "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+        ),
+        (
+            "68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c",
+            "hamster diagram private dutch cause delay private meat slide toddler razor book happy fancy gospel tennis maple dilemma loan word shrug inflict delay length",
+        ),
+    ];
+
+    #[test]
+    fn test_wordlist() {
+        let words = english();
+        assert_eq!(words.len(), 2048);
+        assert_eq!(words[0], "abandon");
+        assert_eq!(words[2047], "zoo");
+        assert!(words.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_vectors() {
+        for (entropy, mnemonic) in VECTORS {
+            let entropy = unhex(entropy);
+            assert_eq!(&entropy_to_mnemonic(&entropy).unwrap(), mnemonic);
+            assert_eq!(mnemonic_to_entropy(mnemonic).unwrap(), entropy);
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects_bad_mnemonics() {
+        // Wrong checksum word
+        assert!(mnemonic_to_entropy(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon"
+        )
+        .is_err());
+        // Unknown word
+        assert!(mnemonic_to_entropy(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abacus"
+        )
+        .is_err());
+        // Bad length
+        assert!(mnemonic_to_entropy("abandon about").is_err());
+    }
+
+    #[test]
+    fn test_generate() {
+        for words in VALID_WORD_COUNTS {
+            let mnemonic = generate(words).unwrap();
+            assert_eq!(mnemonic.split(' ').count(), words);
+            assert!(mnemonic_to_entropy(&mnemonic).is_ok());
+        }
+        assert!(generate(13).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::passphrase::Capitalization;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// A lightweight, flexible password generator
+#[derive(Parser, Debug)]
+#[command(name = "genpass")]
+#[command(version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Minimum number of numeric characters (0-9)
+    #[arg(short = 'n', long)]
+    pub min_numeric: Option<usize>,
+
+    /// Maximum number of numeric characters (0-9)
+    #[arg(short = 'N', long)]
+    pub max_numeric: Option<usize>,
+
+    /// Minimum number of lowercase letters (a-z)
+    #[arg(short = 'a', long)]
+    pub min_lower: Option<usize>,
+
+    /// Maximum number of lowercase letters (a-z)
+    #[arg(short = 'A', long)]
+    pub max_lower: Option<usize>,
+
+    /// Minimum number of uppercase letters (A-Z)
+    #[arg(short = 'u', long)]
+    pub min_upper: Option<usize>,
+
+    /// Maximum number of uppercase letters (A-Z)
+    #[arg(short = 'U', long)]
+    pub max_upper: Option<usize>,
+
+    /// Minimum number of symbol characters
+    #[arg(short = 's', long)]
+    pub min_symbol: Option<usize>,
+
+    /// Maximum number of symbol characters
+    #[arg(short = 'S', long)]
+    pub max_symbol: Option<usize>,
+
+    /// Minimum number of hiragana (full-width, e.g. あ); enables hiragana
+    #[arg(long)]
+    pub min_hiragana: Option<usize>,
+
+    /// Maximum number of hiragana; enables hiragana
+    #[arg(long)]
+    pub max_hiragana: Option<usize>,
+
+    /// Minimum number of katakana (full-width, e.g. ア); enables katakana
+    #[arg(long)]
+    pub min_katakana: Option<usize>,
+
+    /// Maximum number of katakana; enables katakana
+    #[arg(long)]
+    pub max_katakana: Option<usize>,
+
+    /// Exact password length (shorthand for setting both min and max length)
+    #[arg(short = 'l', long, conflicts_with_all = ["min_length", "max_length"])]
+    pub length: Option<usize>,
+
+    /// Minimum total password length
+    #[arg(long)]
+    pub min_length: Option<usize>,
+
+    /// Maximum total password length
+    #[arg(long)]
+    pub max_length: Option<usize>,
+
+    /// Maximum password size in UTF-8 bytes (kana take 3 bytes each)
+    #[arg(long)]
+    pub max_bytes: Option<usize>,
+
+    /// Define which symbol characters to use
+    #[arg(long, default_value = "!@#$%^&*()_+-=[]{}|;:,.<>?")]
+    pub symbols: String,
+
+    /// Exclude visually ambiguous characters (0/O, 1/l/I, etc.)
+    #[arg(long)]
+    pub exclude_ambiguous: bool,
+
+    /// Generate passwords shaped by a hashcat-style mask (e.g. "?u?d?d?d?d?d?d?s")
+    #[arg(long, conflicts_with_all = ["words", "bip39", "pronounceable", "markov"])]
+    pub mask: Option<String>,
+
+    /// Custom charset for ?1 in a mask (may use ?l ?u ?d ?s ?a)
+    #[arg(long, value_name = "CHARS")]
+    pub charset1: Option<String>,
+
+    /// Custom charset for ?2 in a mask
+    #[arg(long, value_name = "CHARS")]
+    pub charset2: Option<String>,
+
+    /// Custom charset for ?3 in a mask
+    #[arg(long, value_name = "CHARS")]
+    pub charset3: Option<String>,
+
+    /// Custom charset for ?4 in a mask
+    #[arg(long, value_name = "CHARS")]
+    pub charset4: Option<String>,
+
+    /// Generate passwords from a template, e.g. "{word}-{word}-{digits:4}{symbol}"
+    #[arg(long, conflicts_with_all = ["words", "bip39", "pronounceable", "markov", "mask"])]
+    pub template: Option<String>,
+
+    /// Generate passwords matching a validation regex, e.g. "^(?=.*\d)[A-Za-z\d]{12,16}$"
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        conflicts_with_all = ["words", "bip39", "pronounceable", "markov", "mask", "template"]
+    )]
+    pub regex: Option<String>,
+
+    /// Generate word-like passwords from a Markov model saved with `genpass train`
+    #[arg(long, value_name = "MODEL", conflicts_with_all = ["words", "bip39", "pronounceable"])]
+    pub markov: Option<String>,
+
+    /// Target entropy in bits; picks the shortest length or word count that reaches it
+    #[arg(long, value_name = "BITS")]
+    pub min_entropy: Option<f64>,
+
+    /// Generate pronounceable passwords from consonant/vowel syllables
+    #[arg(long, conflicts_with_all = ["words", "bip39"])]
+    pub pronounceable: bool,
+
+    /// Pick uniformly among all passwords that satisfy the constraints
+    #[arg(
+        long,
+        conflicts_with_all = ["words", "bip39", "pronounceable", "markov", "mask", "template", "regex"]
+    )]
+    pub uniform: bool,
+
+    /// Number of passwords to generate
+    #[arg(long, short = 'c', default_value = "1")]
+    pub count: usize,
+
+    /// Copy the last generated password to clipboard (macOS only)
+    #[arg(short = 'C', long)]
+    pub copy: bool,
+
+    /// Generate a passphrase of this many words instead of a character password
+    #[arg(short = 'w', long)]
+    pub words: Option<usize>,
+
+    /// Separator placed between passphrase words (default: "-")
+    #[arg(long)]
+    pub separator: Option<String>,
+
+    /// Capitalization of passphrase words: lower, first, upper or random
+    #[arg(long)]
+    pub capitalize: Option<Capitalization>,
+
+    /// Make passphrase words from random romaji syllables (default: 3 per word)
+    #[arg(long, value_name = "SYLLABLES", num_args = 0..=1, default_missing_value = "3", conflicts_with = "wordlist")]
+    pub romaji: Option<usize>,
+
+    /// Insert a random digit into the passphrase
+    #[arg(long)]
+    pub add_digit: bool,
+
+    /// Insert a random symbol into the passphrase
+    #[arg(long)]
+    pub add_symbol: bool,
+
+    /// Wordlist for passphrases: "short" for the built-in EFF short list, or a file
+    /// with one word per line, optionally diceware-numbered
+    #[arg(long)]
+    pub wordlist: Option<PathBuf>,
+
+    /// Choose passphrase words from physical dice rolls typed on stdin
+    #[arg(long)]
+    pub dice: bool,
+
+    /// Like --dice, but combine each roll with the system RNG
+    #[arg(long, conflicts_with = "dice")]
+    pub dice_mix: bool,
+
+    /// Generate a BIP39 mnemonic of this many words (12, 15, 18, 21 or 24)
+    #[arg(long, value_name = "WORDS", conflicts_with_all = ["words", "verify"])]
+    pub bip39: Option<usize>,
+
+    /// Report the entropy of the selected generator and exit
+    #[arg(long)]
+    pub entropy: bool,
+
+    /// Check the words and checksum of an existing BIP39 mnemonic and exit
+    ///
+    /// The mnemonic is read from stdin when no value or "-" is given. Passing
+    /// it as an argument leaves it in the shell history and the process list.
+    #[arg(
+        long,
+        value_name = "MNEMONIC",
+        num_args = 0..=1,
+        default_missing_value = "-"
+    )]
+    pub verify: Option<String>,
+
+    /// Mix extra entropy from a file into the system RNG ("-" reads stdin)
+    #[arg(long, value_name = "FILE")]
+    pub extra_entropy: Option<PathBuf>,
+
+    /// Replay passwords from a fixed seed, for tests and bug reports (INSECURE)
+    #[arg(long, value_name = "SEED", conflicts_with = "extra_entropy")]
+    pub insecure_seed: Option<u64>,
+
+    /// Load configuration from a named profile
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Skip unknown keys and bad values in a config with a warning instead
+    /// of failing (for profiles written by newer versions)
+    #[arg(long)]
+    pub lenient_config: bool,
+
+    /// Save current options to a named config (default: "default")
+    #[arg(long)]
+    pub save_config: Option<String>,
+
+    /// List all available saved configurations
+    #[arg(long)]
+    pub list_configs: bool,
+
+    /// Display current configuration settings
+    #[arg(long)]
+    pub status: Option<String>,
+
+    /// Interactive wizard mode for configuring password generation
+    #[arg(long)]
+    pub wizard: bool,
+
+    /// Set a named configuration as the default
+    #[arg(long)]
+    pub set_default: Option<String>,
+}
+
+/// Subcommands
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Train a Markov model on a text file for use with --markov
+    Train {
+        /// Name to save the model under (~/.genpass/models/NAME)
+        name: String,
+
+        /// Text file whose words the model should imitate
+        corpus: PathBuf,
+
+        /// Size of the character n-grams (2-6); larger sizes copy the corpus more closely
+        #[arg(long, default_value = "3")]
+        ngram: usize,
+    },
+
+    /// Run known-answer tests for the built-in crypto and health tests of the RNG
+    Selftest,
+
+    /// Manage saved configuration profiles
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+/// Actions of the config subcommand
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Convert key=value profiles to TOML, keeping backups in ~/.genpass/backups
+    Migrate,
+}
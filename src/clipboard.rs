@@ -1,4 +1,5 @@
 use std::io;
+#[cfg(target_os = "macos")]
 use std::process::{Command, Stdio};
 
 /// Copy text to clipboard using platform-specific methods
@@ -10,6 +11,7 @@ pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
 
     #[cfg(not(target_os = "macos"))]
     {
+        let _ = text;
         Err(io::Error::new(
             io::ErrorKind::Unsupported,
             "Clipboard support is only available on macOS",
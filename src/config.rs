@@ -1,9 +1,153 @@
+use crate::generator::{PasswordConstraints, UNIFORM_MAX_LENGTH};
+use crate::passphrase::{Capitalization, DiceMode};
+use crate::toml::{self, Value};
+use crate::wordlist::Wordlist;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::io::{self, BufRead, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Longest password considered when searching for an entropy target,
+/// which is measured on the uniform keyspace
+pub const ENTROPY_MAX_LENGTH: usize = UNIFORM_MAX_LENGTH;
+
+/// Every key a legacy key=value config file may set
+const KEYS: &[&str] = &[
+    "min-numeric",
+    "max-numeric",
+    "min-lower",
+    "max-lower",
+    "min-upper",
+    "max-upper",
+    "min-symbol",
+    "max-symbol",
+    "min-hiragana",
+    "max-hiragana",
+    "min-katakana",
+    "max-katakana",
+    "length",
+    "min-length",
+    "max-length",
+    "max-bytes",
+    "symbols",
+    "exclude-ambiguous",
+    "pronounceable",
+    "uniform",
+    "mask",
+    "charset1",
+    "charset2",
+    "charset3",
+    "charset4",
+    "template",
+    "regex",
+    "markov",
+    "min-entropy",
+    "count",
+    "copy",
+    "words",
+    "separator",
+    "capitalize",
+    "add-digit",
+    "add-symbol",
+    "wordlist",
+    "romaji",
+    "dice",
+    "extends",
+];
+
+/// The type a TOML setting takes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Integer,
+    /// Integers are accepted too
+    Float,
+    Boolean,
+    String,
+    /// An array of strings
+    Strings,
+}
+
+impl Kind {
+    fn describe(self) -> &'static str {
+        match self {
+            Kind::Integer => "an integer",
+            Kind::Float => "a number",
+            Kind::Boolean => "a boolean",
+            Kind::String => "a string",
+            Kind::Strings => "an array of strings",
+        }
+    }
+}
+
+/// Where each setting lives in a TOML profile, as (setting, table, key,
+/// type), grouped by table in the order they are written
+///
+/// Settings are named by their legacy keys, except for `charsets`, which
+/// replaces `charset1` to `charset4` with one array. An empty table means
+/// the top level, before any `[table]` header.
+const TOML_LAYOUT: &[(&str, &str, &str, Kind)] = &[
+    ("extends", "", "extends", Kind::String),
+    ("length", "length", "exact", Kind::Integer),
+    ("min-length", "length", "min", Kind::Integer),
+    ("max-length", "length", "max", Kind::Integer),
+    ("max-bytes", "length", "max-bytes", Kind::Integer),
+    ("min-entropy", "length", "min-entropy", Kind::Float),
+    ("min-numeric", "classes", "min-numeric", Kind::Integer),
+    ("max-numeric", "classes", "max-numeric", Kind::Integer),
+    ("min-lower", "classes", "min-lower", Kind::Integer),
+    ("max-lower", "classes", "max-lower", Kind::Integer),
+    ("min-upper", "classes", "min-upper", Kind::Integer),
+    ("max-upper", "classes", "max-upper", Kind::Integer),
+    ("min-symbol", "classes", "min-symbol", Kind::Integer),
+    ("max-symbol", "classes", "max-symbol", Kind::Integer),
+    ("min-hiragana", "classes", "min-hiragana", Kind::Integer),
+    ("max-hiragana", "classes", "max-hiragana", Kind::Integer),
+    ("min-katakana", "classes", "min-katakana", Kind::Integer),
+    ("max-katakana", "classes", "max-katakana", Kind::Integer),
+    (
+        "exclude-ambiguous",
+        "classes",
+        "exclude-ambiguous",
+        Kind::Boolean,
+    ),
+    ("symbols", "symbols", "chars", Kind::String),
+    ("uniform", "generator", "uniform", Kind::Boolean),
+    ("pronounceable", "generator", "pronounceable", Kind::Boolean),
+    ("template", "generator", "template", Kind::String),
+    ("regex", "generator", "regex", Kind::String),
+    ("markov", "generator", "markov", Kind::String),
+    ("mask", "mask", "pattern", Kind::String),
+    ("charsets", "mask", "charsets", Kind::Strings),
+    ("words", "passphrase", "words", Kind::Integer),
+    ("separator", "passphrase", "separator", Kind::String),
+    ("capitalize", "passphrase", "capitalize", Kind::String),
+    ("add-digit", "passphrase", "add-digit", Kind::Boolean),
+    ("add-symbol", "passphrase", "add-symbol", Kind::Boolean),
+    ("wordlist", "passphrase", "wordlist", Kind::String),
+    ("romaji", "passphrase", "romaji", Kind::Integer),
+    ("dice", "passphrase", "dice", Kind::String),
+    ("count", "output", "count", Kind::Integer),
+    ("copy", "output", "copy", Kind::Boolean),
+];
+
+/// A problem on one line of a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Line number, counting from 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
 
 /// Configuration that can be saved and loaded from ~/.genpassconfig
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Config {
     pub min_numeric: Option<usize>,
     pub max_numeric: Option<usize>,
@@ -13,13 +157,38 @@ pub struct Config {
     pub max_upper: Option<usize>,
     pub min_symbol: Option<usize>,
     pub max_symbol: Option<usize>,
+    pub min_hiragana: Option<usize>,
+    pub max_hiragana: Option<usize>,
+    pub min_katakana: Option<usize>,
+    pub max_katakana: Option<usize>,
     pub length: Option<usize>,
     pub min_length: Option<usize>,
     pub max_length: Option<usize>,
+    pub max_bytes: Option<usize>,
     pub symbols: Option<String>,
     pub exclude_ambiguous: Option<bool>,
+    pub pronounceable: Option<bool>,
+    pub uniform: Option<bool>,
+    pub mask: Option<String>,
+    pub charsets: [Option<String>; 4],
+    pub template: Option<String>,
+    pub regex: Option<String>,
+    pub markov: Option<String>,
+    pub min_entropy: Option<f64>,
     pub count: Option<usize>,
     pub copy: Option<bool>,
+    pub words: Option<usize>,
+    pub separator: Option<String>,
+    pub capitalize: Option<Capitalization>,
+    pub add_digit: Option<bool>,
+    pub add_symbol: Option<bool>,
+    pub wordlist: Option<String>,
+    pub romaji: Option<usize>,
+    pub dice: Option<DiceMode>,
+    /// Profile whose settings fill in the ones this profile leaves unset
+    pub extends: Option<String>,
+    /// Inherited settings, with the profile each came from and its value
+    origins: BTreeMap<&'static str, (String, Value)>,
 }
 
 impl Config {
@@ -40,20 +209,191 @@ impl Config {
     }
 
     /// Load configuration from a named config
-    /// Returns default config if file doesn't exist
+    /// Returns default config if no name is given and the default file
+    /// doesn't exist
+    ///
+    /// # Errors
+    /// Fails with `InvalidData` on any unknown key, bad value or repeated
+    /// key, listing each as `path:line: problem`
     pub fn load(name: Option<&str>) -> io::Result<Self> {
-        let path = Self::config_path(name)?;
+        let (config, diagnostics) = Self::load_lenient(name)?;
+        if diagnostics.is_empty() {
+            Ok(config)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                diagnostics.join("\n"),
+            ))
+        }
+    }
+
+    /// Load configuration like `load`, skipping the lines it would reject
+    ///
+    /// Meant for profiles written by newer versions. The skipped lines are
+    /// returned as `path:line: problem`.
+    pub fn load_lenient(name: Option<&str>) -> io::Result<(Self, Vec<String>)> {
+        Self::load_chain(&Self::config_dir()?, name, &mut Vec::new())
+    }
+
+    /// Load a profile from `dir` and the profiles it extends, where `chain`
+    /// holds the profiles that extend this one
+    fn load_chain(
+        dir: &Path,
+        name: Option<&str>,
+        chain: &mut Vec<String>,
+    ) -> io::Result<(Self, Vec<String>)> {
+        let path = dir.join(name.unwrap_or("default"));
 
         if !path.exists() {
-            return Ok(Self::default());
+            return match name {
+                // A misspelled profile name must not fall back to defaults
+                Some(name) => Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Configuration '{}' not found", name),
+                )),
+                None => Ok((Self::default(), Vec::new())),
+            };
         }
 
         let content = fs::read_to_string(&path)?;
-        Self::parse(&content)
+        let (mut config, diagnostics) = Self::parse_lenient(&content);
+        let mut diagnostics: Vec<String> =
+            diagnostics.iter().map(|d| Self::locate(&path, d)).collect();
+        let Some(parent) = config.extends.clone() else {
+            return Ok((config, diagnostics));
+        };
+
+        chain.push(name.unwrap_or("default").to_string());
+        let problem = if chain.contains(&parent) {
+            Some(format!(
+                "extends '{}', which forms a cycle: {} -> {}",
+                parent,
+                chain.join(" -> "),
+                parent
+            ))
+        } else if !dir.join(&parent).exists() {
+            Some(format!("extends '{}', which was not found", parent))
+        } else {
+            None
+        };
+
+        if let Some(message) = problem {
+            // Lenient loading goes on without the parent
+            config.extends = None;
+            let line = Self::extends_line(&content);
+            diagnostics.push(Self::locate(&path, &Diagnostic { line, message }));
+            return Ok((config, diagnostics));
+        }
+
+        let (parent_config, parent_diagnostics) = Self::load_chain(dir, Some(&parent), chain)?;
+        diagnostics.extend(parent_diagnostics);
+        Ok((config.inherit(parent_config, &parent), diagnostics))
+    }
+
+    /// The line of a profile that sets `extends`
+    fn extends_line(content: &str) -> usize {
+        content
+            .lines()
+            .position(|line| {
+                line.split('=')
+                    .next()
+                    .is_some_and(|key| key.trim().trim_matches('"') == "extends")
+            })
+            .map_or(1, |index| index + 1)
+    }
+
+    /// Fill the settings this profile leaves unset from its parent
+    ///
+    /// The mask charsets are inherited together, as one setting.
+    fn inherit(mut self, parent: Self, parent_name: &str) -> Self {
+        for &(setting, _, _, _) in TOML_LAYOUT {
+            if setting != "extends"
+                && self.toml_value(setting).is_none()
+                && let Some(value) = parent.toml_value(setting)
+            {
+                let origin = parent
+                    .origins
+                    .get(setting)
+                    .map_or(parent_name, |(name, _)| name.as_str());
+                self.origins.insert(setting, (origin.to_string(), value));
+            }
+        }
+
+        let charsets = if self.charsets.iter().any(Option::is_some) {
+            self.charsets
+        } else {
+            parent.charsets
+        };
+        Self {
+            min_numeric: self.min_numeric.or(parent.min_numeric),
+            max_numeric: self.max_numeric.or(parent.max_numeric),
+            min_lower: self.min_lower.or(parent.min_lower),
+            max_lower: self.max_lower.or(parent.max_lower),
+            min_upper: self.min_upper.or(parent.min_upper),
+            max_upper: self.max_upper.or(parent.max_upper),
+            min_symbol: self.min_symbol.or(parent.min_symbol),
+            max_symbol: self.max_symbol.or(parent.max_symbol),
+            min_hiragana: self.min_hiragana.or(parent.min_hiragana),
+            max_hiragana: self.max_hiragana.or(parent.max_hiragana),
+            min_katakana: self.min_katakana.or(parent.min_katakana),
+            max_katakana: self.max_katakana.or(parent.max_katakana),
+            length: self.length.or(parent.length),
+            min_length: self.min_length.or(parent.min_length),
+            max_length: self.max_length.or(parent.max_length),
+            max_bytes: self.max_bytes.or(parent.max_bytes),
+            symbols: self.symbols.or(parent.symbols),
+            exclude_ambiguous: self.exclude_ambiguous.or(parent.exclude_ambiguous),
+            pronounceable: self.pronounceable.or(parent.pronounceable),
+            uniform: self.uniform.or(parent.uniform),
+            mask: self.mask.or(parent.mask),
+            charsets,
+            template: self.template.or(parent.template),
+            regex: self.regex.or(parent.regex),
+            markov: self.markov.or(parent.markov),
+            min_entropy: self.min_entropy.or(parent.min_entropy),
+            count: self.count.or(parent.count),
+            copy: self.copy.or(parent.copy),
+            words: self.words.or(parent.words),
+            separator: self.separator.or(parent.separator),
+            capitalize: self.capitalize.or(parent.capitalize),
+            add_digit: self.add_digit.or(parent.add_digit),
+            add_symbol: self.add_symbol.or(parent.add_symbol),
+            wordlist: self.wordlist.or(parent.wordlist),
+            romaji: self.romaji.or(parent.romaji),
+            dice: self.dice.or(parent.dice),
+            extends: self.extends,
+            origins: self.origins,
+        }
+    }
+
+    /// The profile a setting was inherited from, if it still has the
+    /// inherited value
+    fn inherited(&self, setting: &str) -> Option<&str> {
+        self.origins
+            .get(setting)
+            .filter(|(_, value)| self.toml_value(setting).as_ref() == Some(value))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// A setting as a TOML value, unless it is unset or inherited
+    fn own_value(&self, setting: &str) -> Option<Value> {
+        self.toml_value(setting)
+            .filter(|_| self.inherited(setting).is_none())
+    }
+
+    /// A diagnostic in the usual `path:line: problem` form
+    fn locate(path: &Path, diagnostic: &Diagnostic) -> String {
+        format!(
+            "{}:{}: {}",
+            path.display(),
+            diagnostic.line,
+            diagnostic.message
+        )
     }
 
     /// Save configuration to a named config file
-    /// Overwrites existing file if present
+    /// Overwrites existing file if present, keeping the comments and
+    /// layout of a TOML profile
     pub fn save(&self, name: Option<&str>) -> io::Result<()> {
         // Ensure config directory exists
         let dir = Self::config_dir()?;
@@ -62,61 +402,277 @@ impl Config {
         }
 
         let path = Self::config_path(name)?;
-        let mut content = String::new();
-
-        content.push_str("# genpass configuration file\n");
-        content.push_str("# Generated automatically - edit with caution\n\n");
-
-        if let Some(v) = self.min_numeric {
-            content.push_str(&format!("min-numeric={}\n", v));
-        }
-        if let Some(v) = self.max_numeric {
-            content.push_str(&format!("max-numeric={}\n", v));
-        }
-        if let Some(v) = self.min_lower {
-            content.push_str(&format!("min-lower={}\n", v));
-        }
-        if let Some(v) = self.max_lower {
-            content.push_str(&format!("max-lower={}\n", v));
-        }
-        if let Some(v) = self.min_upper {
-            content.push_str(&format!("min-upper={}\n", v));
+        if self.extends.as_deref() == Some(name.unwrap_or("default")) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Configuration '{}' cannot extend itself",
+                    name.unwrap_or("default")
+                ),
+            ));
         }
-        if let Some(v) = self.max_upper {
-            content.push_str(&format!("max-upper={}\n", v));
+        let existing = fs::read_to_string(&path).ok();
+        fs::write(&path, self.render_toml(existing.as_deref()))
+    }
+
+    /// The TOML profile `save` writes
+    ///
+    /// If the file being replaced is a TOML profile, its comments, layout
+    /// and unknown keys are kept and only the changed settings rewritten.
+    /// Inherited settings are left to the parent profile.
+    fn render_toml(&self, existing: Option<&str>) -> String {
+        let Some(content) = existing.filter(|content| Self::is_toml(content)) else {
+            return self.render_fresh();
+        };
+        let (document, diagnostics) = toml::parse(content);
+        if !diagnostics.is_empty() {
+            return self.render_fresh();
         }
-        if let Some(v) = self.min_symbol {
-            content.push_str(&format!("min-symbol={}\n", v));
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut kept: Vec<Option<String>> =
+            lines.iter().map(|line| Some(line.to_string())).collect();
+        // New lines to add after a line number, and tables to add at the end
+        let mut inserted: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        let mut new_tables: Vec<(&str, Vec<String>)> = Vec::new();
+
+        for &(setting, table, key, _) in TOML_LAYOUT {
+            let value = self.own_value(setting);
+            let entry = document
+                .entries
+                .iter()
+                .find(|e| e.table == table && e.key == key);
+
+            match (value, entry) {
+                (Some(value), Some(entry)) if value != entry.value => {
+                    let indent: String = lines[entry.line - 1]
+                        .chars()
+                        .take_while(|c| c.is_whitespace())
+                        .collect();
+                    let comment = entry
+                        .comment
+                        .as_ref()
+                        .map(|comment| format!(" {}", comment))
+                        .unwrap_or_default();
+                    kept[entry.line - 1] =
+                        Some(format!("{}{} = {}{}", indent, key, value, comment));
+                    for line in &mut kept[entry.line..entry.end_line] {
+                        *line = None;
+                    }
+                }
+                (Some(value), None) => {
+                    let line = format!("{} = {}", key, value);
+                    let anchor = document
+                        .entries
+                        .iter()
+                        .filter(|e| e.table == table)
+                        .map(|e| e.end_line)
+                        .max()
+                        .or_else(|| {
+                            document
+                                .tables
+                                .iter()
+                                .find(|t| t.name == table)
+                                .map(|t| t.line)
+                        })
+                        // Top-level keys go after the opening comments
+                        .or_else(|| {
+                            table.is_empty().then(|| {
+                                lines
+                                    .iter()
+                                    .take_while(|line| line.trim_start().starts_with('#'))
+                                    .count()
+                            })
+                        });
+                    match anchor {
+                        Some(anchor) => inserted.entry(anchor).or_default().push(line),
+                        None => match new_tables.iter_mut().find(|(t, _)| *t == table) {
+                            Some((_, lines)) => lines.push(line),
+                            None => new_tables.push((table, vec![line])),
+                        },
+                    }
+                }
+                (None, Some(entry)) => {
+                    for line in &mut kept[entry.line - 1..entry.end_line] {
+                        *line = None;
+                    }
+                }
+                _ => {}
+            }
         }
-        if let Some(v) = self.max_symbol {
-            content.push_str(&format!("max-symbol={}\n", v));
+
+        let mut output = String::new();
+        for line in inserted.remove(&0).unwrap_or_default() {
+            output.push_str(&line);
+            output.push('\n');
         }
-        if let Some(v) = self.length {
-            content.push_str(&format!("length={}\n", v));
+        for (index, line) in kept.into_iter().enumerate() {
+            let added = inserted.remove(&(index + 1)).unwrap_or_default();
+            for line in line.into_iter().chain(added) {
+                output.push_str(&line);
+                output.push('\n');
+            }
         }
-        if let Some(v) = self.min_length {
-            content.push_str(&format!("min-length={}\n", v));
+        for (table, lines) in new_tables {
+            output.push_str(&format!("\n[{}]\n", table));
+            for line in lines {
+                output.push_str(&line);
+                output.push('\n');
+            }
         }
-        if let Some(v) = self.max_length {
-            content.push_str(&format!("max-length={}\n", v));
+        output
+    }
+
+    /// A new TOML profile with every setting that is set
+    fn render_fresh(&self) -> String {
+        let mut content = String::from("# genpass configuration file\n");
+        let mut current = "";
+        for &(setting, table, key, _) in TOML_LAYOUT {
+            if let Some(value) = self.own_value(setting) {
+                if table != current {
+                    content.push_str(&format!("\n[{}]\n", table));
+                    current = table;
+                }
+                content.push_str(&format!("{} = {}\n", key, value));
+            }
         }
-        if let Some(ref v) = self.symbols {
-            content.push_str(&format!("symbols={}\n", v));
+        content
+    }
+
+    /// A setting as a TOML value, or None if it is unset
+    fn toml_value(&self, setting: &str) -> Option<Value> {
+        // Limits beyond i64 are all far too large to generate anyway
+        let number =
+            |v: Option<usize>| v.map(|v| Value::Integer(i64::try_from(v).unwrap_or(i64::MAX)));
+        let flag = |v: Option<bool>| v.map(Value::Boolean);
+        let text = |v: &Option<String>| v.clone().map(Value::String);
+
+        match setting {
+            "length" => number(self.length),
+            "min-length" => number(self.min_length),
+            "max-length" => number(self.max_length),
+            "max-bytes" => number(self.max_bytes),
+            "min-entropy" => self.min_entropy.map(Value::Float),
+            "min-numeric" => number(self.min_numeric),
+            "max-numeric" => number(self.max_numeric),
+            "min-lower" => number(self.min_lower),
+            "max-lower" => number(self.max_lower),
+            "min-upper" => number(self.min_upper),
+            "max-upper" => number(self.max_upper),
+            "min-symbol" => number(self.min_symbol),
+            "max-symbol" => number(self.max_symbol),
+            "min-hiragana" => number(self.min_hiragana),
+            "max-hiragana" => number(self.max_hiragana),
+            "min-katakana" => number(self.min_katakana),
+            "max-katakana" => number(self.max_katakana),
+            "exclude-ambiguous" => flag(self.exclude_ambiguous),
+            "symbols" => text(&self.symbols),
+            "uniform" => flag(self.uniform),
+            "pronounceable" => flag(self.pronounceable),
+            "template" => text(&self.template),
+            "regex" => text(&self.regex),
+            "markov" => text(&self.markov),
+            "mask" => text(&self.mask),
+            "charsets" => {
+                // Unset charsets before the last set one become empty strings
+                let last = self.charsets.iter().rposition(Option::is_some)?;
+                let charsets = self.charsets[..=last]
+                    .iter()
+                    .map(|charset| Value::String(charset.clone().unwrap_or_default()))
+                    .collect();
+                Some(Value::Array(charsets))
+            }
+            "words" => number(self.words),
+            "separator" => text(&self.separator),
+            "capitalize" => self.capitalize.map(|v| Value::String(v.to_string())),
+            "add-digit" => flag(self.add_digit),
+            "add-symbol" => flag(self.add_symbol),
+            "wordlist" => text(&self.wordlist),
+            "romaji" => number(self.romaji),
+            "dice" => self.dice.map(|v| Value::String(v.to_string())),
+            "count" => number(self.count),
+            "copy" => flag(self.copy),
+            "extends" => text(&self.extends),
+            _ => None,
         }
-        if let Some(v) = self.exclude_ambiguous {
-            content.push_str(&format!("exclude-ambiguous={}\n", v));
+    }
+
+    /// Whether a profile is TOML rather than the legacy key=value format,
+    /// which never has `[table]` lines and writes `extends` unquoted
+    ///
+    /// A TOML profile that only extends another has no tables at all.
+    pub fn is_toml(content: &str) -> bool {
+        content.lines().any(|line| {
+            let line = line.trim_start();
+            line.starts_with('[')
+                || line.split_once('=').is_some_and(|(key, value)| {
+                    key.trim() == "extends" && value.trim_start().starts_with('"')
+                })
+        })
+    }
+
+    /// Whether a profile has settings in the legacy format, which
+    /// `migrate` would convert
+    fn is_legacy(content: &str) -> bool {
+        let has_settings = content.lines().any(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        });
+        has_settings && !Self::is_toml(content)
+    }
+
+    /// Whether a saved profile is still in the legacy format
+    pub fn needs_migration(name: Option<&str>) -> io::Result<bool> {
+        let path = Self::config_path(name)?;
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Self::is_legacy(&content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
         }
-        if let Some(v) = self.count {
-            content.push_str(&format!("count={}\n", v));
+    }
+
+    /// Convert a legacy key=value profile to TOML in place
+    ///
+    /// The original is first copied to `~/.genpass/backups/`. Returns the
+    /// path of the backup, or None if the profile needed no conversion.
+    ///
+    /// # Errors
+    /// Fails without touching the profile if `load` would reject it
+    pub fn migrate(name: &str) -> io::Result<Option<PathBuf>> {
+        let path = Self::config_path(Some(name))?;
+        let backups = Self::config_dir()?.join("backups");
+        Self::migrate_file(&path, &backups)
+    }
+
+    fn migrate_file(path: &Path, backups: &Path) -> io::Result<Option<PathBuf>> {
+        let content = fs::read_to_string(path)?;
+        if !Self::is_legacy(&content) {
+            return Ok(None);
         }
-        if let Some(v) = self.copy {
-            content.push_str(&format!("copy={}\n", v));
+
+        let (config, diagnostics) = Self::parse_lenient(&content);
+        if !diagnostics.is_empty() {
+            let lines: Vec<String> = diagnostics.iter().map(|d| Self::locate(path, d)).collect();
+            return Err(io::Error::new(io::ErrorKind::InvalidData, lines.join("\n")));
         }
 
-        let mut file = fs::File::create(&path)?;
-        file.write_all(content.as_bytes())?;
+        // Never overwrite an earlier backup
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        fs::create_dir_all(backups)?;
+        let mut backup = backups.join(name.as_ref());
+        for n in 1.. {
+            if !backup.exists() {
+                break;
+            }
+            backup = backups.join(format!("{}.{}", name, n));
+        }
+        fs::copy(path, &backup)?;
 
-        Ok(())
+        // Replace the profile in one step, so a failure leaves it intact
+        let temporary = path.with_file_name(format!(".{}.migrating", name));
+        fs::write(&temporary, config.render_toml(None))?;
+        fs::rename(&temporary, path)?;
+        Ok(Some(backup))
     }
 
     /// List all available config names
@@ -130,10 +686,10 @@ impl Config {
         let mut configs = Vec::new();
         for entry in fs::read_dir(&dir)? {
             let entry = entry?;
-            if entry.path().is_file() {
-                if let Some(name) = entry.file_name().to_str() {
-                    configs.push(name.to_string());
-                }
+            if entry.path().is_file()
+                && let Some(name) = entry.file_name().to_str()
+            {
+                configs.push(name.to_string());
             }
         }
 
@@ -162,36 +718,67 @@ impl Config {
     pub fn display(&self, name: Option<&str>) {
         let config_name = name.unwrap_or("default");
         println!("Configuration: {}", config_name);
+        if let Some(ref parent) = self.extends {
+            println!("Extends: {}", parent);
+        }
         println!();
 
         // Character type constraints
         println!("Character Type Constraints:");
-        Self::display_constraint("  Numeric (0-9)", self.min_numeric, self.max_numeric);
-        Self::display_constraint("  Lowercase (a-z)", self.min_lower, self.max_lower);
-        Self::display_constraint("  Uppercase (A-Z)", self.min_upper, self.max_upper);
-        Self::display_constraint("  Symbols", self.min_symbol, self.max_symbol);
+        self.display_constraint(
+            "  Numeric (0-9)",
+            "numeric",
+            self.min_numeric,
+            self.max_numeric,
+        );
+        self.display_constraint("  Lowercase (a-z)", "lower", self.min_lower, self.max_lower);
+        self.display_constraint("  Uppercase (A-Z)", "upper", self.min_upper, self.max_upper);
+        self.display_constraint("  Symbols", "symbol", self.min_symbol, self.max_symbol);
+        if self.min_hiragana.is_some() || self.max_hiragana.is_some() {
+            self.display_constraint(
+                "  Hiragana",
+                "hiragana",
+                self.min_hiragana,
+                self.max_hiragana,
+            );
+        }
+        if self.min_katakana.is_some() || self.max_katakana.is_some() {
+            self.display_constraint(
+                "  Katakana",
+                "katakana",
+                self.min_katakana,
+                self.max_katakana,
+            );
+        }
         println!();
 
         // Password length
         println!("Password Length:");
         if let Some(length) = self.length {
-            println!("  Exact length: {}", length);
+            println!("  Exact length: {}{}", length, self.origin(&["length"]));
         } else {
             if let Some(min) = self.min_length {
-                println!("  Minimum: {}", min);
+                println!("  Minimum: {}{}", min, self.origin(&["min-length"]));
             } else {
                 println!("  Minimum: 16 (default)");
             }
             if let Some(max) = self.max_length {
-                println!("  Maximum: {}", max);
+                println!("  Maximum: {}{}", max, self.origin(&["max-length"]));
             }
         }
+        if let Some(max_bytes) = self.max_bytes {
+            println!(
+                "  Maximum bytes (UTF-8): {}{}",
+                max_bytes,
+                self.origin(&["max-bytes"])
+            );
+        }
         println!();
 
         // Symbol characters
         println!("Symbol Characters:");
         if let Some(ref symbols) = self.symbols {
-            println!("  {}", symbols);
+            println!("  {}{}", symbols, self.origin(&["symbols"]));
         } else {
             println!("  !@#$%^&*()_+-=[]{{}}|;:,.<>? (default)");
         }
@@ -199,32 +786,137 @@ impl Config {
 
         // Other options
         println!("Options:");
+        let origin = self.origin(&["exclude-ambiguous"]);
         match self.exclude_ambiguous {
-            Some(true) => println!("  Exclude ambiguous characters: yes"),
-            Some(false) => println!("  Exclude ambiguous characters: no"),
+            Some(true) => println!("  Exclude ambiguous characters: yes{}", origin),
+            Some(false) => println!("  Exclude ambiguous characters: no{}", origin),
             None => println!("  Exclude ambiguous characters: no (default)"),
         }
+        match self.pronounceable {
+            Some(true) => println!("  Pronounceable: yes{}", self.origin(&["pronounceable"])),
+            _ => println!("  Pronounceable: no"),
+        }
+        match self.uniform {
+            Some(true) => println!("  Uniform sampling: yes{}", self.origin(&["uniform"])),
+            _ => println!("  Uniform sampling: no"),
+        }
+        if let Some(ref mask) = self.mask {
+            println!("  Mask: {}{}", mask, self.origin(&["mask"]));
+            for (i, charset) in self.charsets.iter().enumerate() {
+                if let Some(charset) = charset {
+                    println!(
+                        "  Charset ?{}: {}{}",
+                        i + 1,
+                        charset,
+                        self.origin(&["charsets"])
+                    );
+                }
+            }
+        }
+        if let Some(ref template) = self.template {
+            println!("  Template: {}{}", template, self.origin(&["template"]));
+        }
+        if let Some(ref regex) = self.regex {
+            println!("  Regex: {}{}", regex, self.origin(&["regex"]));
+        }
+        if let Some(ref model) = self.markov {
+            println!("  Markov model: {}{}", model, self.origin(&["markov"]));
+        }
+        if let Some(bits) = self.min_entropy {
+            println!(
+                "  Minimum entropy: {} bits{}",
+                bits,
+                self.origin(&["min-entropy"])
+            );
+        }
         if let Some(count) = self.count {
-            println!("  Password count: {}", count);
+            println!("  Password count: {}{}", count, self.origin(&["count"]));
         } else {
             println!("  Password count: 1 (default)");
         }
+        println!();
+
+        // Passphrase mode
+        println!("Passphrase:");
+        // Romaji selects passphrase mode on its own, as in main
+        if self.words.is_some() || self.romaji.is_some() {
+            match self.words {
+                Some(words) => println!("  Words: {}{}", words, self.origin(&["words"])),
+                None if self.min_entropy.is_some() => {
+                    println!("  Words: as few as reach the entropy target (default)")
+                }
+                None => println!("  Words: 4 (default)"),
+            }
+            match (self.romaji, &self.wordlist) {
+                (Some(syllables), _) => {
+                    println!(
+                        "  Romaji: {} syllables per word{}",
+                        syllables,
+                        self.origin(&["romaji"])
+                    )
+                }
+                (None, Some(path)) => {
+                    println!("  Wordlist: {}{}", path, self.origin(&["wordlist"]))
+                }
+                (None, None) => println!("  Wordlist: EFF short wordlist (default)"),
+            }
+            match self.separator {
+                Some(ref separator) => {
+                    println!(
+                        "  Separator: \"{}\"{}",
+                        separator,
+                        self.origin(&["separator"])
+                    )
+                }
+                None => println!("  Separator: \"-\" (default)"),
+            }
+            match self.capitalize {
+                Some(capitalize) => {
+                    println!(
+                        "  Capitalization: {}{}",
+                        capitalize,
+                        self.origin(&["capitalize"])
+                    )
+                }
+                None => println!("  Capitalization: lower (default)"),
+            }
+            match self.add_digit {
+                Some(true) => println!("  Insert digit: yes{}", self.origin(&["add-digit"])),
+                _ => println!("  Insert digit: no"),
+            }
+            match self.add_symbol {
+                Some(true) => println!("  Insert symbol: yes{}", self.origin(&["add-symbol"])),
+                _ => println!("  Insert symbol: no"),
+            }
+            let origin = self.origin(&["dice"]);
+            match self.dice {
+                Some(DiceMode::Only) => println!("  Dice input: physical dice only{}", origin),
+                Some(DiceMode::Mixed) => println!(
+                    "  Dice input: physical dice mixed with system RNG{}",
+                    origin
+                ),
+                _ => println!("  Dice input: no"),
+            }
+        } else {
+            println!("  Disabled (generating character passwords)");
+        }
     }
 
     /// Helper to display min/max constraints
-    fn display_constraint(label: &str, min: Option<usize>, max: Option<usize>) {
+    fn display_constraint(&self, label: &str, class: &str, min: Option<usize>, max: Option<usize>) {
+        let origin = self.origin(&[&format!("min-{}", class), &format!("max-{}", class)]);
         match (min, max) {
             (Some(min_val), Some(max_val)) if min_val == max_val => {
-                println!("{}: exactly {}", label, min_val);
+                println!("{}: exactly {}{}", label, min_val, origin);
             }
             (Some(min_val), Some(max_val)) => {
-                println!("{}: {} to {}", label, min_val, max_val);
+                println!("{}: {} to {}{}", label, min_val, max_val, origin);
             }
             (Some(min_val), None) => {
-                println!("{}: minimum {}", label, min_val);
+                println!("{}: minimum {}{}", label, min_val, origin);
             }
             (None, Some(max_val)) => {
-                println!("{}: maximum {}", label, max_val);
+                println!("{}: maximum {}{}", label, max_val, origin);
             }
             (None, None) => {
                 println!("{}: no constraint", label);
@@ -232,6 +924,17 @@ impl Config {
         }
     }
 
+    /// Where inherited settings came from, as shown after their values
+    fn origin(&self, settings: &[&str]) -> String {
+        let mut profiles: Vec<&str> = settings.iter().filter_map(|s| self.inherited(s)).collect();
+        profiles.dedup();
+        if profiles.is_empty() {
+            String::new()
+        } else {
+            format!(" (from {})", profiles.join(", "))
+        }
+    }
+
     /// Interactive wizard to configure password generation
     /// Returns: (Config, Option<config_name>, set_as_default)
     pub fn wizard() -> io::Result<(Self, Option<String>, bool)> {
@@ -430,10 +1133,178 @@ impl Config {
     }
 
     /// Parse configuration from a string
-    fn parse(content: &str) -> io::Result<Self> {
+    ///
+    /// # Errors
+    /// Fails with `InvalidData` on any unknown key, bad value or repeated
+    /// key, listing each with its line number
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let (config, diagnostics) = Self::parse_lenient(content);
+        if diagnostics.is_empty() {
+            Ok(config)
+        } else {
+            let lines: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+            Err(io::Error::new(io::ErrorKind::InvalidData, lines.join("\n")))
+        }
+    }
+
+    /// Parse configuration from a string, reporting problems instead of
+    /// failing
+    ///
+    /// Both TOML profiles and the legacy key=value format are read. Lines
+    /// with unknown keys or bad values are skipped. In the legacy format a
+    /// repeated key is reported too, but still overrides the earlier line.
+    pub fn parse_lenient(content: &str) -> (Self, Vec<Diagnostic>) {
+        if Self::is_toml(content) {
+            Self::parse_toml(content)
+        } else {
+            Self::parse_legacy(content)
+        }
+    }
+
+    /// Parse a TOML profile
+    fn parse_toml(content: &str) -> (Self, Vec<Diagnostic>) {
+        let (document, mut diagnostics) = toml::parse(content);
+        let mut config = Self::default();
+        let tables = || {
+            TOML_LAYOUT
+                .iter()
+                .map(|&(_, table, _, _)| table)
+                .filter(|table| !table.is_empty())
+        };
+
+        for table in &document.tables {
+            if !tables().any(|known| known == table.name) {
+                let message = match closest(&table.name, tables()) {
+                    Some(known) => {
+                        format!("unknown table [{}] (did you mean [{}]?)", table.name, known)
+                    }
+                    None => format!("unknown table [{}]", table.name),
+                };
+                diagnostics.push(Diagnostic {
+                    line: table.line,
+                    message,
+                });
+            }
+        }
+
+        for entry in &document.entries {
+            let layout = TOML_LAYOUT
+                .iter()
+                .find(|&&(_, table, key, _)| table == entry.table && key == entry.key);
+            let result = match layout {
+                Some(&(setting, "", key, kind)) => {
+                    config.set_toml(setting, key, kind, &entry.value)
+                }
+                Some(&(setting, table, key, kind)) => {
+                    config.set_toml(setting, &format!("{}.{}", table, key), kind, &entry.value)
+                }
+                // Keys of unknown tables were reported with the table
+                None if !entry.table.is_empty() && !tables().any(|t| t == entry.table) => Ok(()),
+                None => Err(Self::unknown_toml_key(&entry.table, &entry.key)),
+            };
+            if let Err(message) = result {
+                diagnostics.push(Diagnostic {
+                    line: entry.line,
+                    message,
+                });
+            }
+        }
+
+        diagnostics.sort_by_key(|d| d.line);
+        (config, diagnostics)
+    }
+
+    fn unknown_toml_key(table: &str, key: &str) -> String {
+        if table.is_empty() {
+            // Most likely a legacy line pasted into a TOML profile
+            let setting = key
+                .strip_suffix(['1', '2', '3', '4'])
+                .filter(|k| *k == "charset")
+                .map_or(key, |_| "charsets");
+            return match TOML_LAYOUT
+                .iter()
+                .find(|&&(known, _, _, _)| known == setting)
+            {
+                Some(&(_, table, key, _)) => format!(
+                    "unknown key '{}' (did you mean '{}' under [{}]?)",
+                    setting, key, table
+                ),
+                None => format!("unknown key '{}'", key),
+            };
+        }
+
+        let keys = TOML_LAYOUT
+            .iter()
+            .filter(|&&(_, known, _, _)| known == table)
+            .map(|&(_, _, key, _)| key);
+        match closest(key, keys) {
+            Some(known) => format!(
+                "unknown key '{}' in [{}] (did you mean '{}'?)",
+                key, table, known
+            ),
+            None => format!("unknown key '{}' in [{}]", key, table),
+        }
+    }
+
+    /// Set one setting from a TOML value of the right type
+    fn set_toml(
+        &mut self,
+        setting: &str,
+        name: &str,
+        kind: Kind,
+        value: &Value,
+    ) -> Result<(), String> {
+        let text = match (kind, value) {
+            (Kind::Integer | Kind::Float, Value::Integer(i)) => i.to_string(),
+            (Kind::Float, Value::Float(x)) => x.to_string(),
+            (Kind::Boolean, Value::Boolean(b)) => b.to_string(),
+            (Kind::String, Value::String(s)) => s.clone(),
+            (Kind::Strings, Value::Array(items)) => {
+                if items.len() > self.charsets.len() {
+                    return Err(format!(
+                        "{} takes at most {} charsets, not {}",
+                        name,
+                        self.charsets.len(),
+                        items.len()
+                    ));
+                }
+                // An empty string leaves its charset unset
+                let mut charsets: [Option<String>; 4] = Default::default();
+                for (charset, item) in charsets.iter_mut().zip(items) {
+                    match item {
+                        Value::String(s) => *charset = Some(s.clone()).filter(|s| !s.is_empty()),
+                        _ => {
+                            return Err(format!(
+                                "{} must hold strings, not {}",
+                                name,
+                                item.type_name()
+                            ));
+                        }
+                    }
+                }
+                self.charsets = charsets;
+                return Ok(());
+            }
+            _ => {
+                return Err(format!(
+                    "{} must be {}, not {}",
+                    name,
+                    kind.describe(),
+                    value.type_name()
+                ));
+            }
+        };
+        self.assign(setting, name, &text)
+    }
+
+    /// Parse a legacy key=value profile
+    fn parse_legacy(content: &str) -> (Self, Vec<Diagnostic>) {
         let mut config = Self::default();
+        let mut diagnostics = Vec::new();
+        let mut first_lines: Vec<(&str, usize)> = Vec::new();
 
-        for line in content.lines() {
+        for (index, line) in content.lines().enumerate() {
+            let number = index + 1;
             let line = line.trim();
 
             // Skip empty lines and comments
@@ -442,38 +1313,196 @@ impl Config {
             }
 
             // Parse key=value
-            if let Some((key, value)) = line.split_once('=') {
-                let key = key.trim();
-                let value = value.trim();
-
-                match key {
-                    "min-numeric" => config.min_numeric = value.parse().ok(),
-                    "max-numeric" => config.max_numeric = value.parse().ok(),
-                    "min-lower" => config.min_lower = value.parse().ok(),
-                    "max-lower" => config.max_lower = value.parse().ok(),
-                    "min-upper" => config.min_upper = value.parse().ok(),
-                    "max-upper" => config.max_upper = value.parse().ok(),
-                    "min-symbol" => config.min_symbol = value.parse().ok(),
-                    "max-symbol" => config.max_symbol = value.parse().ok(),
-                    "length" => config.length = value.parse().ok(),
-                    "min-length" => config.min_length = value.parse().ok(),
-                    "max-length" => config.max_length = value.parse().ok(),
-                    "symbols" => config.symbols = Some(value.to_string()),
-                    "exclude-ambiguous" => config.exclude_ambiguous = value.parse().ok(),
-                    "count" => config.count = value.parse().ok(),
-                    "copy" => config.copy = value.parse().ok(),
-                    _ => {
-                        // Unknown keys are ignored for forward compatibility
-                    }
+            let Some((key, value)) = line.split_once('=') else {
+                diagnostics.push(Diagnostic {
+                    line: number,
+                    message: format!("expected key=value, found '{}'", line),
+                });
+                continue;
+            };
+            let key = key.trim();
+            let value = value.trim();
+
+            if !KEYS.contains(&key) {
+                let message = match closest(key, KEYS.iter().copied()) {
+                    Some(known) => format!("unknown key '{}' (did you mean '{}'?)", key, known),
+                    None => format!("unknown key '{}'", key),
+                };
+                diagnostics.push(Diagnostic {
+                    line: number,
+                    message,
+                });
+                continue;
+            }
+
+            match first_lines.iter().find(|(seen, _)| *seen == key) {
+                Some(&(_, first)) => diagnostics.push(Diagnostic {
+                    line: number,
+                    message: format!("duplicate key '{}' (first set on line {})", key, first),
+                }),
+                None => first_lines.push((key, number)),
+            }
+
+            if let Err(message) = config.set(key, value) {
+                diagnostics.push(Diagnostic {
+                    line: number,
+                    message,
+                });
+            }
+        }
+
+        (config, diagnostics)
+    }
+
+    /// Set one legacy key from its text value
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = match key {
+            "mask" | "charset1" | "charset2" | "charset3" | "charset4" | "template" | "regex"
+            | "separator" => Self::unquote(value),
+            _ => value,
+        };
+        self.assign(key, key, value)
+    }
+
+    /// Set a setting from its text, naming it `key` in errors
+    fn assign(&mut self, setting: &str, key: &str, value: &str) -> Result<(), String> {
+        match setting {
+            "min-numeric" => self.min_numeric = Some(Self::number(key, value)?),
+            "max-numeric" => self.max_numeric = Some(Self::number(key, value)?),
+            "min-lower" => self.min_lower = Some(Self::number(key, value)?),
+            "max-lower" => self.max_lower = Some(Self::number(key, value)?),
+            "min-upper" => self.min_upper = Some(Self::number(key, value)?),
+            "max-upper" => self.max_upper = Some(Self::number(key, value)?),
+            "min-symbol" => self.min_symbol = Some(Self::number(key, value)?),
+            "max-symbol" => self.max_symbol = Some(Self::number(key, value)?),
+            "min-hiragana" => self.min_hiragana = Some(Self::number(key, value)?),
+            "max-hiragana" => self.max_hiragana = Some(Self::number(key, value)?),
+            "min-katakana" => self.min_katakana = Some(Self::number(key, value)?),
+            "max-katakana" => self.max_katakana = Some(Self::number(key, value)?),
+            "length" => self.length = Some(Self::number(key, value)?),
+            "min-length" => self.min_length = Some(Self::number(key, value)?),
+            "max-length" => self.max_length = Some(Self::number(key, value)?),
+            "max-bytes" => self.max_bytes = Some(Self::number(key, value)?),
+            "symbols" => self.symbols = Some(value.to_string()),
+            "exclude-ambiguous" => self.exclude_ambiguous = Some(Self::flag(key, value)?),
+            "pronounceable" => self.pronounceable = Some(Self::flag(key, value)?),
+            "uniform" => self.uniform = Some(Self::flag(key, value)?),
+            "mask" => self.mask = Some(value.to_string()),
+            "charset1" => self.charsets[0] = Some(value.to_string()),
+            "charset2" => self.charsets[1] = Some(value.to_string()),
+            "charset3" => self.charsets[2] = Some(value.to_string()),
+            "charset4" => self.charsets[3] = Some(value.to_string()),
+            "template" => self.template = Some(value.to_string()),
+            "regex" => self.regex = Some(value.to_string()),
+            "markov" => self.markov = Some(value.to_string()),
+            "min-entropy" => {
+                let bits: f64 = Self::parse_value(key, value, "a number of bits")?;
+                if !bits.is_finite() || bits < 0.0 {
+                    return Err(Self::invalid(key, value, "a number of bits"));
                 }
+                self.min_entropy = Some(bits);
             }
+            "count" => self.count = Some(Self::number(key, value)?),
+            "copy" => self.copy = Some(Self::flag(key, value)?),
+            "words" => self.words = Some(Self::number(key, value)?),
+            "separator" => self.separator = Some(value.to_string()),
+            "capitalize" => self.capitalize = Some(value.parse()?),
+            "add-digit" => self.add_digit = Some(Self::flag(key, value)?),
+            "add-symbol" => self.add_symbol = Some(Self::flag(key, value)?),
+            "wordlist" => self.wordlist = Some(value.to_string()),
+            "romaji" => self.romaji = Some(Self::number(key, value)?),
+            "dice" => self.dice = Some(value.parse()?),
+            "extends" => {
+                // Profiles are files in the config directory
+                if value.is_empty() || value.starts_with('.') || value.contains(['/', '\\']) {
+                    return Err(Self::invalid(key, value, "a profile name"));
+                }
+                self.extends = Some(value.to_string());
+            }
+            _ => return Err(format!("unknown key '{}'", key)),
         }
+        Ok(())
+    }
 
-        Ok(config)
+    fn number(key: &str, value: &str) -> Result<usize, String> {
+        Self::parse_value(key, value, "a whole number")
+    }
+
+    fn flag(key: &str, value: &str) -> Result<bool, String> {
+        Self::parse_value(key, value, "true or false")
+    }
+
+    fn parse_value<T: FromStr>(key: &str, value: &str, expected: &str) -> Result<T, String> {
+        value
+            .parse()
+            .map_err(|_| Self::invalid(key, value, expected))
+    }
+
+    fn invalid(key: &str, value: &str, expected: &str) -> String {
+        format!("invalid {} '{}' (expected {})", key, value, expected)
+    }
+
+    /// Strip one pair of surrounding double quotes, if present
+    fn unquote(value: &str) -> &str {
+        value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value)
+    }
+
+    /// Character password constraints for these options
+    ///
+    /// Lengths default to 16 characters, or with an entropy target to
+    /// anything from the sum of the minimums up to `ENTROPY_MAX_LENGTH`.
+    pub fn password_constraints(&self, symbols: String) -> PasswordConstraints {
+        let (min_length, max_length) = if let Some(length) = self.length {
+            (length, length)
+        } else if self.min_entropy.is_some() {
+            // The target picks the length; min/max-length only bound it
+            let minimums = [
+                self.min_numeric,
+                self.min_lower,
+                self.min_upper,
+                self.min_symbol,
+                self.min_hiragana,
+                self.min_katakana,
+            ]
+            .into_iter()
+            .flatten()
+            .fold(0, usize::saturating_add);
+            (
+                self.min_length.unwrap_or(minimums.max(1)),
+                self.max_length.unwrap_or(ENTROPY_MAX_LENGTH),
+            )
+        } else {
+            let min = self.min_length.unwrap_or(16); // Default minimum length
+            let max = self.max_length.unwrap_or(min); // Default max equals min
+            (min, max)
+        };
+
+        PasswordConstraints {
+            min_numeric: self.min_numeric,
+            max_numeric: self.max_numeric,
+            min_lower: self.min_lower,
+            max_lower: self.max_lower,
+            min_upper: self.min_upper,
+            max_upper: self.max_upper,
+            min_symbol: self.min_symbol,
+            max_symbol: self.max_symbol,
+            min_hiragana: self.min_hiragana,
+            max_hiragana: self.max_hiragana,
+            min_katakana: self.min_katakana,
+            max_katakana: self.max_katakana,
+            min_length,
+            max_length,
+            max_bytes: self.max_bytes,
+            symbols,
+            exclude_ambiguous: self.exclude_ambiguous.unwrap_or(false),
+        }
     }
 
     /// Merge with CLI arguments (CLI args take precedence)
-    pub fn merge_with_cli(&mut self, cli: &crate::Cli) {
+    pub fn merge_with_cli(&mut self, cli: &crate::cli::Cli) {
         if cli.min_numeric.is_some() {
             self.min_numeric = cli.min_numeric;
         }
@@ -498,6 +1527,18 @@ impl Config {
         if cli.max_symbol.is_some() {
             self.max_symbol = cli.max_symbol;
         }
+        if cli.min_hiragana.is_some() {
+            self.min_hiragana = cli.min_hiragana;
+        }
+        if cli.max_hiragana.is_some() {
+            self.max_hiragana = cli.max_hiragana;
+        }
+        if cli.min_katakana.is_some() {
+            self.min_katakana = cli.min_katakana;
+        }
+        if cli.max_katakana.is_some() {
+            self.max_katakana = cli.max_katakana;
+        }
         if cli.length.is_some() {
             self.length = cli.length;
         }
@@ -507,15 +1548,49 @@ impl Config {
         if cli.max_length.is_some() {
             self.max_length = cli.max_length;
         }
+        if cli.max_bytes.is_some() {
+            self.max_bytes = cli.max_bytes;
+        }
         // For symbols, check if it's not the default value
-        if cli.symbols != "!@#$%^&*()_+-=[]{}|;:,.<>?" {
-            self.symbols = Some(cli.symbols.clone());
-        } else if self.symbols.is_none() {
+        if cli.symbols != "!@#$%^&*()_+-=[]{}|;:,.<>?" || self.symbols.is_none() {
             self.symbols = Some(cli.symbols.clone());
         }
         if cli.exclude_ambiguous {
             self.exclude_ambiguous = Some(true);
         }
+        if cli.pronounceable {
+            self.pronounceable = Some(true);
+        }
+        if cli.uniform {
+            self.uniform = Some(true);
+        }
+        if cli.mask.is_some() {
+            self.mask = cli.mask.clone();
+        }
+        for (i, charset) in [&cli.charset1, &cli.charset2, &cli.charset3, &cli.charset4]
+            .into_iter()
+            .enumerate()
+        {
+            if charset.is_some() {
+                self.charsets[i] = charset.clone();
+            }
+        }
+        if cli.template.is_some() {
+            self.template = cli.template.clone();
+        }
+        if cli.regex.is_some() {
+            self.regex = cli.regex.clone();
+        }
+        if cli.markov.is_some() {
+            self.markov = cli.markov.clone();
+        }
+        if cli.min_entropy.is_some() {
+            self.min_entropy = cli.min_entropy;
+            // A target on the command line replaces a saved fixed length
+            if cli.length.is_none() {
+                self.length = None;
+            }
+        }
         // For count, only override if not default
         if cli.count != 1 {
             self.count = Some(cli.count);
@@ -525,9 +1600,67 @@ impl Config {
         if cli.copy {
             self.copy = Some(true);
         }
+        if cli.words.is_some() {
+            self.words = cli.words;
+        }
+        if cli.separator.is_some() {
+            self.separator = cli.separator.clone();
+        }
+        if cli.capitalize.is_some() {
+            self.capitalize = cli.capitalize;
+        }
+        if cli.add_digit {
+            self.add_digit = Some(true);
+        }
+        if cli.add_symbol {
+            self.add_symbol = Some(true);
+        }
+        if let Some(ref path) = cli.wordlist {
+            // Store an absolute path so saved profiles work from any
+            // directory, but keep the names of built-in lists
+            let path = match path.to_str() {
+                Some(name) if Wordlist::builtin(name).is_some() => path.clone(),
+                _ => std::path::absolute(path).unwrap_or_else(|_| path.clone()),
+            };
+            self.wordlist = Some(path.display().to_string());
+        }
+        if cli.romaji.is_some() {
+            self.romaji = cli.romaji;
+        }
+        if cli.dice_mix {
+            self.dice = Some(DiceMode::Mixed);
+        } else if cli.dice {
+            self.dice = Some(DiceMode::Only);
+        }
     }
 }
 
+/// The candidate closest to a misspelled word, if any is close enough
+fn closest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|known| (edit_distance(word, known), known))
+        .filter(|&(distance, _)| distance <= 2)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, known)| known)
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -541,6 +1674,10 @@ max-numeric=5
 min-length=16
 symbols=!@#$
 exclude-ambiguous=true
+pronounceable=true
+uniform=true
+markov=english
+min-entropy=60.5
 count=3
 "#;
 
@@ -550,6 +1687,10 @@ count=3
         assert_eq!(config.min_length, Some(16));
         assert_eq!(config.symbols, Some("!@#$".to_string()));
         assert_eq!(config.exclude_ambiguous, Some(true));
+        assert_eq!(config.pronounceable, Some(true));
+        assert_eq!(config.uniform, Some(true));
+        assert_eq!(config.markov, Some("english".to_string()));
+        assert_eq!(config.min_entropy, Some(60.5));
         assert_eq!(config.count, Some(3));
     }
 
@@ -561,6 +1702,27 @@ count=3
         assert_eq!(config.count, None);
     }
 
+    #[test]
+    fn test_password_constraints() {
+        let content = "min-numeric=2\nmax-length=20\n";
+        let constraints = Config::parse(content)
+            .unwrap()
+            .password_constraints("!".to_string());
+        assert_eq!(constraints.min_numeric, Some(2));
+        assert_eq!((constraints.min_length, constraints.max_length), (16, 20));
+
+        // Minimums too large to add up are rejected rather than overflowing
+        let content = "min-entropy=64\nmin-numeric=18446744073709551615\nmin-lower=1\n";
+        let constraints = Config::parse(content)
+            .unwrap()
+            .password_constraints("!".to_string());
+        assert_eq!(
+            (constraints.min_length, constraints.max_length),
+            (usize::MAX, ENTROPY_MAX_LENGTH)
+        );
+        assert!(constraints.validate().is_err());
+    }
+
     #[test]
     fn test_parse_with_unknown_keys() {
         let content = r#"
@@ -569,7 +1731,387 @@ unknown-key=value
 future-option=123
 "#;
 
-        let config = Config::parse(content).unwrap();
+        assert!(Config::parse(content).is_err());
+
+        // Lenient parsing skips them for profiles from newer versions
+        let (config, diagnostics) = Config::parse_lenient(content);
         assert_eq!(config.min_numeric, Some(2));
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic {
+                    line: 3,
+                    message: "unknown key 'unknown-key'".to_string()
+                },
+                Diagnostic {
+                    line: 4,
+                    message: "unknown key 'future-option'".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_strict_diagnostics() {
+        let content = "# work profile\nmin-numeric=two\nexclude-ambigous=true\nlength=20\nlength=24\nuniform\ncapitalize=title\n";
+
+        let e = Config::parse(content).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            e.to_string(),
+            "line 2: invalid min-numeric 'two' (expected a whole number)\n\
+             line 3: unknown key 'exclude-ambigous' (did you mean 'exclude-ambiguous'?)\n\
+             line 5: duplicate key 'length' (first set on line 4)\n\
+             line 6: expected key=value, found 'uniform'\n\
+             line 7: invalid capitalization 'title' (expected lower, first, upper or random)"
+        );
+
+        // Lenient parsing keeps what it can, and the last of a repeated key
+        let (config, diagnostics) = Config::parse_lenient(content);
+        assert_eq!(diagnostics.len(), 5);
+        assert_eq!(config.min_numeric, None);
+        assert_eq!(config.exclude_ambiguous, None);
+        assert_eq!(config.length, Some(24));
+    }
+
+    #[test]
+    fn test_parse_invalid_values() {
+        for line in [
+            "count=-1",
+            "copy=yes",
+            "min-entropy=nan",
+            "min-entropy=-5",
+            "max-bytes=",
+            "dice=sometimes",
+        ] {
+            assert!(Config::parse(line).is_err(), "{}", line);
+        }
+        // Empty strings are meaningful for text values
+        assert_eq!(
+            Config::parse("symbols=").unwrap().symbols,
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn test_every_key_is_settable() {
+        for key in KEYS {
+            let e = Config::default()
+                .set(key, "\u{0}")
+                .err()
+                .unwrap_or_default();
+            assert!(!e.starts_with("unknown key"), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_saved_config_parses_strictly() {
+        let mut config = Config::parse(
+            "min-numeric=2\nsymbols=!@#\nmask=\" ?d\"\nmin-entropy=60.5\ncapitalize=first\ndice=mixed\n",
+        )
+        .unwrap();
+        config.charsets[1] = Some("abc".to_string());
+
+        let rendered = config.render_toml(None);
+        assert!(Config::is_toml(&rendered));
+        let reloaded = Config::parse(&rendered).unwrap();
+        assert_eq!(reloaded, config);
+        assert_eq!(reloaded.mask, Some(" ?d".to_string()));
+        assert_eq!(reloaded.charsets[0], None);
+        assert_eq!(reloaded.charsets[1], Some("abc".to_string()));
+        assert_eq!(reloaded.min_entropy, Some(60.5));
+        assert_eq!(reloaded.dice, Some(DiceMode::Mixed));
+    }
+
+    #[test]
+    fn test_toml_round_trip_of_every_setting() {
+        let legacy: String = KEYS
+            .iter()
+            .map(|&key| {
+                let value = match key {
+                    "min-entropy" => "72.5",
+                    "capitalize" => "random",
+                    "dice" => "mixed",
+                    "mask" | "charset1" | "charset2" | "charset3" | "charset4" | "template"
+                    | "regex" | "separator" | "symbols" | "wordlist" | "markov" => "\"a#b\"",
+                    _ if key.starts_with("min-") || key.starts_with("max-") => "3",
+                    "length" | "words" | "romaji" | "count" => "12",
+                    _ => "true",
+                };
+                format!("{}={}\n", key, value)
+            })
+            .collect();
+        let config = Config::parse(&legacy).unwrap();
+        let rendered = config.render_toml(None);
+
+        assert!(rendered.contains("[passphrase]\nwords = 12\n"));
+        assert_eq!(Config::parse(&rendered).unwrap(), config);
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let content = "# team profile\n[length]\nmin = 16 # policy\nmin-entropy = 80\n\n[mask]\ncharsets = [\"\", \"abc\"]\n\n[output]\ncopy = true\n";
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.min_length, Some(16));
+        assert_eq!(config.min_entropy, Some(80.0));
+        assert_eq!(config.charsets[0], None);
+        assert_eq!(config.charsets[1], Some("abc".to_string()));
+        assert_eq!(config.copy, Some(true));
+    }
+
+    #[test]
+    fn test_parse_toml_diagnostics() {
+        let content = "min-length = 8\n[lenght]\nmin = 2\n[classes]\nmin-numric = 2\nmin-upper = \"2\"\n[output]\ncount = -1\n";
+        let e = Config::parse(content).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "line 1: unknown key 'min-length' (did you mean 'min' under [length]?)\n\
+             line 2: unknown table [lenght] (did you mean [length]?)\n\
+             line 5: unknown key 'min-numric' in [classes] (did you mean 'min-numeric'?)\n\
+             line 6: classes.min-upper must be an integer, not a string\n\
+             line 8: invalid output.count '-1' (expected a whole number)"
+        );
+
+        let e =
+            Config::parse("[mask]\ncharsets = [\"a\", \"b\", \"c\", \"d\", \"e\"]\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "line 2: mask.charsets takes at most 4 charsets, not 5"
+        );
+    }
+
+    #[test]
+    fn test_render_toml_keeps_comments() {
+        let existing = "# Work policy\n\n[length]\n  min = 12 # raised in 2025\nmax = 20\n\n[extra]\nnote = \"kept\"\n\n[classes]\nmin-symbol = 1\n";
+        let mut config = Config::parse_lenient(existing).0;
+        config.min_length = Some(16);
+        config.max_length = None;
+        config.min_numeric = Some(2);
+        config.copy = Some(true);
+
+        assert_eq!(
+            config.render_toml(Some(existing)),
+            "# Work policy\n\n[length]\n  min = 16 # raised in 2025\n\n[extra]\nnote = \"kept\"\n\n[classes]\nmin-symbol = 1\nmin-numeric = 2\n\n[output]\ncopy = true\n"
+        );
+    }
+
+    #[test]
+    fn test_migrate_file() {
+        let dir = std::env::temp_dir().join(format!("genpass-migrate-{}", std::process::id()));
+        let backups = dir.join("backups");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("work");
+        let legacy = "# work\nmin-length=16\nmask=\"?d?d\"\ncharset2=xyz\n";
+        fs::write(&path, legacy).unwrap();
+        assert!(Config::is_legacy(legacy));
+        assert!(!Config::is_legacy("# no settings\n"));
+
+        let backup = Config::migrate_file(&path, &backups).unwrap().unwrap();
+        assert_eq!(backup, backups.join("work"));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), legacy);
+        let migrated = fs::read_to_string(&path).unwrap();
+        assert!(Config::is_toml(&migrated));
+        assert_eq!(
+            Config::parse(&migrated).unwrap(),
+            Config::parse(legacy).unwrap()
+        );
+
+        // Already migrated
+        assert_eq!(Config::migrate_file(&path, &backups).unwrap(), None);
+
+        // A second legacy file keeps the first backup
+        fs::write(&path, legacy).unwrap();
+        let backup = Config::migrate_file(&path, &backups).unwrap().unwrap();
+        assert_eq!(backup, backups.join("work.1"));
+
+        // Invalid files are left alone
+        fs::write(&path, "min-length=abc\n").unwrap();
+        let e = Config::migrate_file(&path, &backups).unwrap_err();
+        assert!(
+            e.to_string()
+                .ends_with("work:1: invalid min-length 'abc' (expected a whole number)")
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "min-length=abc\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_chain() {
+        let dir = std::env::temp_dir().join(format!("genpass-extends-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("base"), "count=3\nmin-symbol=1\n").unwrap();
+        fs::write(
+            dir.join("corporate"),
+            "extends = \"base\"\n\n[length]\nmin = 16\n\n[classes]\nmin-symbol = 2\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("team"),
+            "# team\nextends = \"corporate\"\n\n[classes]\nmin-symbol = 0\n",
+        )
+        .unwrap();
+
+        let (config, diagnostics) =
+            Config::load_chain(&dir, Some("team"), &mut Vec::new()).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(config.extends, Some("corporate".to_string()));
+
+        // A profile that only extends another has no tables but is TOML
+        fs::write(dir.join("alias"), "extends = \"base\"\n").unwrap();
+        let (alias, diagnostics) =
+            Config::load_chain(&dir, Some("alias"), &mut Vec::new()).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(alias.count, Some(3));
+        assert!(!Config::is_toml("extends=base\ncount=3\n"));
+        assert_eq!(config.min_length, Some(16));
+        assert_eq!(config.min_symbol, Some(0));
+        assert_eq!(config.count, Some(3));
+        assert_eq!(config.inherited("min-length"), Some("corporate"));
+        assert_eq!(config.inherited("count"), Some("base"));
+        assert_eq!(config.inherited("min-symbol"), None);
+
+        // Saving writes only what the profile itself sets
+        assert_eq!(
+            config.render_toml(None),
+            "# genpass configuration file\nextends = \"corporate\"\n\n[classes]\nmin-symbol = 0\n"
+        );
+        let mut overridden = config.clone();
+        overridden.count = Some(5);
+        assert_eq!(overridden.inherited("count"), None);
+        assert!(
+            overridden
+                .render_toml(None)
+                .contains("[output]\ncount = 5\n")
+        );
+
+        // An existing profile gains the key after its opening comments
+        let mut updated = Config::parse("# team\n[classes]\nmin-symbol = 0\n").unwrap();
+        updated.extends = Some("corporate".to_string());
+        assert_eq!(
+            updated.render_toml(Some("# team\n[classes]\nmin-symbol = 0\n")),
+            "# team\nextends = \"corporate\"\n[classes]\nmin-symbol = 0\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_chain_problems() {
+        let dir = std::env::temp_dir().join(format!("genpass-cycle-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a"), "extends=b\n").unwrap();
+        fs::write(dir.join("b"), "length=12\nextends=a\n").unwrap();
+        fs::write(
+            dir.join("c"),
+            "# orphan\nextends = \"nobody\"\n[output]\ncount = 2\n",
+        )
+        .unwrap();
+
+        let (config, diagnostics) = Config::load_chain(&dir, Some("a"), &mut Vec::new()).unwrap();
+        assert_eq!(
+            diagnostics,
+            vec![format!(
+                "{}:2: extends 'a', which forms a cycle: a -> b -> a",
+                dir.join("b").display()
+            )]
+        );
+        // Lenient loading keeps everything but the broken link
+        assert_eq!(config.length, Some(12));
+
+        let (config, diagnostics) = Config::load_chain(&dir, Some("c"), &mut Vec::new()).unwrap();
+        assert_eq!(
+            diagnostics,
+            vec![format!(
+                "{}:2: extends 'nobody', which was not found",
+                dir.join("c").display()
+            )]
+        );
+        assert_eq!(config.extends, None);
+        assert_eq!(config.count, Some(2));
+
+        assert!(Config::parse("extends=../secrets").is_err());
+        assert!(Config::parse("[output]\nextends = \"a\"\n").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("exclude-ambigous", "exclude-ambiguous"), 1);
+        assert_eq!(edit_distance("min-numric", "min-numeric"), 1);
+        assert_eq!(edit_distance("lenght", "length"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(closest("colour", KEYS.iter().copied()), None);
+    }
+
+    #[test]
+    fn test_parse_mask() {
+        let content = r#"
+mask="?1?d?d?d?d?d?d?s"
+charset1="?l?u"
+charset4=abc
+"#;
+
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.mask, Some("?1?d?d?d?d?d?d?s".to_string()));
+        assert_eq!(config.charsets[0], Some("?l?u".to_string()));
+        assert_eq!(config.charsets[1], None);
+        assert_eq!(config.charsets[3], Some("abc".to_string()));
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let content = "template=\"svc-{hex:32} \"\n";
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.template, Some("svc-{hex:32} ".to_string()));
+    }
+
+    #[test]
+    fn test_parse_regex() {
+        let content = "regex=\"^(?=.*\\d)[a-z\\d ]{8,12}$\"\n";
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.regex, Some("^(?=.*\\d)[a-z\\d ]{8,12}$".to_string()));
+    }
+
+    #[test]
+    fn test_parse_kana_options() {
+        let content = r#"
+min-hiragana=2
+max-katakana=4
+max-bytes=32
+romaji=2
+"#;
+
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.min_hiragana, Some(2));
+        assert_eq!(config.max_hiragana, None);
+        assert_eq!(config.max_katakana, Some(4));
+        assert_eq!(config.max_bytes, Some(32));
+        assert_eq!(config.romaji, Some(2));
+    }
+
+    #[test]
+    fn test_parse_passphrase_options() {
+        let content = r#"
+words=6
+separator=" "
+capitalize=first
+add-digit=true
+wordlist=/home/user/eff_large_wordlist.txt
+dice=mixed
+"#;
+
+        let config = Config::parse(content).unwrap();
+        assert_eq!(config.words, Some(6));
+        assert_eq!(config.separator, Some(" ".to_string()));
+        assert_eq!(config.capitalize, Some(Capitalization::First));
+        assert_eq!(config.add_digit, Some(true));
+        assert_eq!(config.add_symbol, None);
+        assert_eq!(
+            config.wordlist,
+            Some("/home/user/eff_large_wordlist.txt".to_string())
+        );
+        assert_eq!(config.dice, Some(DiceMode::Mixed));
     }
 }
@@ -0,0 +1,405 @@
+use crate::generator::{CharClass, MAX_LENGTH, PasswordConstraints};
+use std::fmt;
+
+/// Constraints that contradict each other
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// What is wrong, in terms of option names
+    pub problem: String,
+    /// Minimal changes, any one of which resolves the conflict on its own
+    pub fixes: Vec<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.problem)?;
+        if !self.fixes.is_empty() {
+            write!(f, "; {}", self.fixes.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Every conflict in a set of constraints
+///
+/// Empty exactly when at least one password satisfies them, so the
+/// generator never runs out of options halfway through a password.
+pub fn check(constraints: &PasswordConstraints) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let classes = constraints.char_classes();
+    let (min_length, max_length) = (constraints.min_length, constraints.max_length);
+
+    for class in &classes {
+        let name = class.name;
+        if let Some(max) = class.max
+            && class.min > max
+        {
+            conflicts.push(Conflict {
+                problem: format!(
+                    "min-{} ({}) is greater than max-{} ({})",
+                    name, class.min, name, max
+                ),
+                fixes: vec![
+                    format!("lower min-{} to {}", name, max),
+                    format!("raise max-{} to {}", name, class.min),
+                ],
+            });
+        }
+
+        if class.min > 0 && class.chars.is_empty() {
+            let mut fixes = vec![format!("set min-{} to 0", name)];
+            let problem = if name == "symbol" && constraints.symbols.is_empty() {
+                fixes.push("add characters to symbols".to_string());
+                format!("min-{} is {} but no symbols are set", name, class.min)
+            } else {
+                fixes.push("turn off exclude-ambiguous".to_string());
+                if name == "symbol" {
+                    fixes.push("add unambiguous characters to symbols".to_string());
+                }
+                format!(
+                    "min-{} is {} but every {} character is ambiguous",
+                    name, class.min, name
+                )
+            };
+            conflicts.push(Conflict { problem, fixes });
+        }
+    }
+
+    if min_length > max_length {
+        conflicts.push(Conflict {
+            problem: format!(
+                "min-length ({}) is greater than max-length ({})",
+                min_length, max_length
+            ),
+            fixes: [
+                Some(format!("lower min-length to {}", max_length)),
+                (min_length <= MAX_LENGTH).then(|| format!("raise max-length to {}", min_length)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        });
+    }
+
+    if max_length > MAX_LENGTH {
+        conflicts.push(Conflict {
+            problem: format!(
+                "max-length ({}) is above the limit of {}",
+                max_length, MAX_LENGTH
+            ),
+            fixes: vec![format!("lower max-length to {}", MAX_LENGTH)],
+        });
+    }
+
+    // The minimums must fit in every allowed length
+    let total_min = total_min(&classes);
+    for (option, length) in [("max-length", max_length), ("min-length", min_length)] {
+        if total_min > length {
+            let mut fixes = Vec::new();
+            if total_min <= MAX_LENGTH {
+                fixes.push(format!("raise {} to {}", option, total_min));
+            }
+            fixes.extend(lower_minimums(&classes, total_min - length));
+            conflicts.push(Conflict {
+                problem: format!(
+                    "the minimums add up to {} characters but {} is {}",
+                    total_min, option, length
+                ),
+                fixes,
+            });
+            // A shorter max-length implies the min-length conflict too
+            break;
+        }
+    }
+
+    // The maximums must leave room for the shortest password
+    if let Some(capacity) = capacity(&classes)
+        && capacity < min_length
+    {
+        let deficit = min_length - capacity;
+        let mut fixes: Vec<String> = classes
+            .iter()
+            .filter(|class| !class.chars.is_empty())
+            .filter_map(|class| {
+                let max = class.max?;
+                Some(format!(
+                    "raise max-{} to {}",
+                    class.name,
+                    max.saturating_add(deficit)
+                ))
+            })
+            .collect();
+        if capacity >= total_min {
+            fixes.push(format!("lower min-length to {}", capacity));
+        }
+        conflicts.push(Conflict {
+            problem: format!(
+                "the maximums allow at most {} characters but min-length is {}",
+                capacity, min_length
+            ),
+            fixes,
+        });
+    }
+
+    // Bytes only grow with the length, so the shortest password must fit
+    if let Some(max_bytes) = constraints.max_bytes
+        && let Some(needed) = min_bytes(&classes, min_length)
+        && needed > max_bytes
+    {
+        let mut fixes = vec![format!("raise max-bytes to {}", needed)];
+        // Every character takes at least a byte
+        let shorter = (total_min..min_length.min(max_bytes.saturating_add(1)))
+            .rev()
+            .find(|&length| min_bytes(&classes, length).is_some_and(|bytes| bytes <= max_bytes));
+        if let Some(length) = shorter {
+            fixes.push(format!("lower min-length to {}", length));
+        }
+        conflicts.push(Conflict {
+            problem: format!(
+                "a password of {} characters needs at least {} bytes but max-bytes is {}",
+                min_length, needed, max_bytes
+            ),
+            fixes,
+        });
+    }
+
+    conflicts
+}
+
+/// Sum of the class minimums, saturating rather than overflowing
+fn total_min(classes: &[CharClass]) -> usize {
+    classes
+        .iter()
+        .fold(0, |total, class| total.saturating_add(class.min))
+}
+
+/// Ways to shed `excess` characters from the minimums with a single change
+fn lower_minimums(classes: &[CharClass], excess: usize) -> Vec<String> {
+    classes
+        .iter()
+        .filter(|class| class.min >= excess)
+        .map(|class| format!("lower min-{} to {}", class.name, class.min - excess))
+        .collect()
+}
+
+/// Most characters the classes can hold together, or None if unlimited
+fn capacity(classes: &[CharClass]) -> Option<usize> {
+    classes.iter().try_fold(0usize, |total, class| {
+        if class.chars.is_empty() {
+            Some(total)
+        } else {
+            class.max.map(|max| total.saturating_add(max))
+        }
+    })
+}
+
+/// Fewest bytes a password of `length` characters can take, filling the
+/// slots beyond the minimums with the narrowest characters first
+///
+/// None if the minimums or maximums rule out that length altogether.
+fn min_bytes(classes: &[CharClass], length: usize) -> Option<usize> {
+    let mut slots = length.checked_sub(total_min(classes))?;
+    let mut bytes = classes.iter().try_fold(0usize, |total, class| {
+        total.checked_add(class.min.checked_mul(class.width)?)
+    })?;
+
+    let mut by_width: Vec<&CharClass> = classes
+        .iter()
+        .filter(|class| !class.chars.is_empty())
+        .collect();
+    by_width.sort_by_key(|class| class.width);
+    for class in by_width {
+        let room = class
+            .max
+            .map_or(slots, |max| max.saturating_sub(class.min).min(slots));
+        bytes = bytes.checked_add(room.checked_mul(class.width)?)?;
+        slots -= room;
+    }
+    (slots == 0).then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraints() -> PasswordConstraints {
+        PasswordConstraints {
+            min_numeric: None,
+            max_numeric: None,
+            min_lower: None,
+            max_lower: None,
+            min_upper: None,
+            max_upper: None,
+            min_symbol: None,
+            max_symbol: None,
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
+            min_length: 16,
+            max_length: 16,
+            max_bytes: None,
+            symbols: "!@#$".to_string(),
+            exclude_ambiguous: false,
+        }
+    }
+
+    #[test]
+    fn test_feasible() {
+        assert!(check(&constraints()).is_empty());
+
+        let mut c = constraints();
+        c.min_numeric = Some(4);
+        c.max_numeric = Some(4);
+        c.min_symbol = Some(2);
+        c.min_length = 6;
+        assert!(check(&c).is_empty());
+    }
+
+    #[test]
+    fn test_maximums_below_min_length() {
+        let mut c = constraints();
+        c.max_numeric = Some(4);
+        c.max_lower = Some(4);
+        c.max_upper = Some(4);
+        c.max_symbol = Some(2);
+
+        let conflicts = check(&c);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].problem,
+            "the maximums allow at most 14 characters but min-length is 16"
+        );
+        assert!(
+            conflicts[0]
+                .fixes
+                .contains(&"raise max-symbol to 4".to_string())
+        );
+        assert!(
+            conflicts[0]
+                .fixes
+                .contains(&"lower min-length to 14".to_string())
+        );
+    }
+
+    #[test]
+    fn test_all_conflicts_at_once() {
+        let mut c = constraints();
+        c.min_numeric = Some(5);
+        c.max_numeric = Some(3);
+        c.min_symbol = Some(1);
+        c.symbols = String::new();
+        c.min_length = 20;
+
+        let conflicts = check(&c);
+        assert_eq!(conflicts.len(), 3, "{:?}", conflicts);
+        assert_eq!(
+            conflicts[0].to_string(),
+            "min-numeric (5) is greater than max-numeric (3); lower min-numeric to 3 or raise max-numeric to 5"
+        );
+        assert!(conflicts[1].problem.contains("no symbols are set"));
+        assert!(conflicts[2].problem.contains("max-length"));
+
+        let message = c.validate().unwrap_err();
+        assert!(
+            message.starts_with("3 conflicting constraints:"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_minimums_exceed_length() {
+        let mut c = constraints();
+        c.min_numeric = Some(10);
+        c.min_upper = Some(8);
+
+        let conflicts = check(&c);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].fixes,
+            [
+                "raise max-length to 18",
+                "lower min-numeric to 8",
+                "lower min-upper to 6"
+            ]
+        );
+
+        // Longer passwords are allowed, but not the shortest ones
+        c.max_length = 20;
+        let conflicts = check(&c);
+        assert_eq!(conflicts.len(), 1);
+        assert!(
+            conflicts[0]
+                .fixes
+                .contains(&"raise min-length to 18".to_string())
+        );
+    }
+
+    #[test]
+    fn test_empty_after_filtering() {
+        let mut c = constraints();
+        c.symbols = "|".to_string();
+        c.exclude_ambiguous = true;
+        assert!(check(&c).is_empty());
+
+        c.min_symbol = Some(1);
+        let conflicts = check(&c);
+        assert_eq!(conflicts.len(), 1);
+        assert!(
+            conflicts[0]
+                .fixes
+                .contains(&"turn off exclude-ambiguous".to_string())
+        );
+    }
+
+    #[test]
+    fn test_huge_limits() {
+        let mut c = constraints();
+        c.min_numeric = Some(usize::MAX);
+        c.min_lower = Some(usize::MAX);
+        c.min_length = 8;
+        c.max_length = usize::MAX;
+        c.max_bytes = Some(8);
+
+        let conflicts = check(&c);
+        assert_eq!(
+            conflicts[0].to_string(),
+            format!(
+                "max-length ({}) is above the limit of {}; lower max-length to {}",
+                usize::MAX,
+                MAX_LENGTH,
+                MAX_LENGTH
+            )
+        );
+        assert!(conflicts.iter().any(|c| c.problem.contains("minimums")));
+
+        // No fix points past the length limit
+        let fixes: Vec<&String> = conflicts.iter().flat_map(|c| &c.fixes).collect();
+        assert!(
+            fixes.iter().all(|fix| !fix.starts_with("raise max-length")),
+            "{:?}",
+            fixes
+        );
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        // Four kana take 12 bytes, and two more characters take at least 2
+        let mut c = constraints();
+        c.min_hiragana = Some(4);
+        c.min_length = 6;
+        c.max_length = 6;
+        c.max_bytes = Some(13);
+
+        let conflicts = check(&c);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].fixes,
+            ["raise max-bytes to 14", "lower min-length to 5"]
+        );
+
+        c.max_bytes = Some(14);
+        assert!(check(&c).is_empty());
+    }
+}
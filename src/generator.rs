@@ -1,13 +1,90 @@
-use crate::random::SecureRandom;
+use crate::bigint::BigUint;
+use crate::feasibility;
+use crate::random::{RandomSource, SecureRandom};
+use std::collections::BTreeMap;
 use std::io;
 
 /// Character sets for password generation
-const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
-const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
-const NUMERIC: &str = "0123456789";
+pub(crate) const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+pub(crate) const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+pub(crate) const NUMERIC: &str = "0123456789";
+
+/// Hiragana: the 46 basic kana and their voiced forms
+///
+/// Each is a single precomposed code point, so output is already NFC.
+/// Small kana and the combining voicing marks are left out.
+pub const HIRAGANA: &str = "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわをん\
+                            がぎぐげござじずぜぞだぢづでどばびぶべぼぱぴぷぺぽ";
+
+/// Katakana counterparts of `HIRAGANA`
+pub const KATAKANA: &str = "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワヲン\
+                            ガギグゲゴザジズゼゾダヂヅデドバビブベボパピプペポ";
+
+/// Romaji syllables (Hepburn) for romaji passphrases
+///
+/// ん, ぢ and づ are left out: "n" runs into the next syllable and
+/// the others are spelled like じ and ず. Every syllable ends in its only
+/// vowel, so a run of syllables splits back apart in exactly one way.
+pub const ROMAJI_SYLLABLES: &[&str] = &[
+    "a", "i", "u", "e", "o", "ka", "ki", "ku", "ke", "ko", "sa", "shi", "su", "se", "so", "ta",
+    "chi", "tsu", "te", "to", "na", "ni", "nu", "ne", "no", "ha", "hi", "fu", "he", "ho", "ma",
+    "mi", "mu", "me", "mo", "ya", "yu", "yo", "ra", "ri", "ru", "re", "ro", "wa", "wo", "ga", "gi",
+    "gu", "ge", "go", "za", "ji", "zu", "ze", "zo", "da", "de", "do", "ba", "bi", "bu", "be", "bo",
+    "pa", "pi", "pu", "pe", "po",
+];
+
+/// Longest password the generator will build, in characters
+///
+/// Lengths are enumerated up front, so an unbounded `max-length` would
+/// hang instead of failing.
+pub const MAX_LENGTH: usize = 4096;
+
+/// Longest password whose keyspace is counted exactly
+///
+/// Counting takes time roughly cubic in the length: about two seconds at
+/// this limit, but minutes at `MAX_LENGTH`.
+pub const UNIFORM_MAX_LENGTH: usize = 1024;
 
 /// Visually ambiguous characters to exclude
-const AMBIGUOUS_CHARS: &[char] = &['0', 'O', '1', 'l', 'I', '|'];
+///
+/// The kana へ/べ/ぺ look the same in hiragana and katakana.
+const AMBIGUOUS_CHARS: &[char] = &[
+    '0', 'O', '1', 'l', 'I', '|', 'へ', 'べ', 'ぺ', 'ヘ', 'ベ', 'ペ',
+];
+
+/// The characters of a set, without the ambiguous ones if requested
+pub(crate) fn filter_ambiguous(s: &str, exclude_ambiguous: bool) -> Vec<char> {
+    if exclude_ambiguous {
+        s.chars()
+            .filter(|c| !AMBIGUOUS_CHARS.contains(c))
+            .collect()
+    } else {
+        s.chars().collect()
+    }
+}
+
+/// Drop repeated characters, keeping the first of each
+///
+/// User-supplied symbols may repeat or overlap the other sets, and each
+/// character must count once when sampling.
+pub(crate) fn unique_chars(chars: Vec<char>) -> Vec<char> {
+    let mut unique = Vec::with_capacity(chars.len());
+    for c in chars {
+        if !unique.contains(&c) {
+            unique.push(c);
+        }
+    }
+    unique
+}
+
+/// Build a random word from romaji syllables
+pub fn romaji_word(syllables: usize, rng: &mut dyn RandomSource) -> io::Result<String> {
+    let mut word = String::new();
+    for _ in 0..syllables {
+        word.push_str(ROMAJI_SYLLABLES[rng.random_range(ROMAJI_SYLLABLES.len())?]);
+    }
+    Ok(word)
+}
 
 /// Constraints for password generation
 #[derive(Debug, Clone)]
@@ -20,73 +97,388 @@ pub struct PasswordConstraints {
     pub max_upper: Option<usize>,
     pub min_symbol: Option<usize>,
     pub max_symbol: Option<usize>,
+    pub min_hiragana: Option<usize>,
+    pub max_hiragana: Option<usize>,
+    pub min_katakana: Option<usize>,
+    pub max_katakana: Option<usize>,
     pub min_length: usize,
     pub max_length: usize,
+    /// Maximum size of the password in UTF-8 bytes
+    pub max_bytes: Option<usize>,
     pub symbols: String,
     pub exclude_ambiguous: bool,
 }
 
 impl PasswordConstraints {
-    /// Validate that constraints are internally consistent
+    /// Check that at least one password satisfies the constraints
+    ///
+    /// Every conflict is reported at once, each with the changes that
+    /// would resolve it; see `feasibility::check`.
     pub fn validate(&self) -> Result<(), String> {
-        // Check min <= max for each character type
-        if let (Some(min), Some(max)) = (self.min_numeric, self.max_numeric) {
-            if min > max {
-                return Err("min_numeric cannot be greater than max_numeric".to_string());
+        let conflicts = feasibility::check(self);
+        match conflicts.as_slice() {
+            [] => Ok(()),
+            [conflict] => Err(conflict.to_string()),
+            conflicts => {
+                let mut message = format!("{} conflicting constraints:", conflicts.len());
+                for conflict in conflicts {
+                    message.push_str(&format!("\n  - {}", conflict));
+                }
+                Err(message)
             }
         }
-        if let (Some(min), Some(max)) = (self.min_lower, self.max_lower) {
-            if min > max {
-                return Err("min_lower cannot be greater than max_lower".to_string());
-            }
+    }
+
+    /// Check that a password satisfies the constraints
+    ///
+    /// Each character counts towards the first class that contains it, so
+    /// symbols that are also letters or digits count as those.
+    pub fn verify(&self, password: &str) -> Result<(), String> {
+        let length = password.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return Err(format!(
+                "length {} is outside {} to {}",
+                length, self.min_length, self.max_length
+            ));
         }
-        if let (Some(min), Some(max)) = (self.min_upper, self.max_upper) {
-            if min > max {
-                return Err("min_upper cannot be greater than max_upper".to_string());
-            }
+        if let Some(max_bytes) = self.max_bytes
+            && password.len() > max_bytes
+        {
+            return Err(format!(
+                "{} bytes is more than max-bytes ({})",
+                password.len(),
+                max_bytes
+            ));
         }
-        if let (Some(min), Some(max)) = (self.min_symbol, self.max_symbol) {
-            if min > max {
-                return Err("min_symbol cannot be greater than max_symbol".to_string());
+
+        let classes = self.char_classes();
+        let mut counts = vec![0; classes.len()];
+        for c in password.chars() {
+            let Some(i) = classes.iter().position(|class| class.chars.contains(&c)) else {
+                return Err(format!("'{}' is not an allowed character", c));
+            };
+            counts[i] += 1;
+        }
+        for (class, count) in classes.iter().zip(counts) {
+            if count < class.min || class.max.is_some_and(|max| count > max) {
+                return Err(format!(
+                    "{} {} characters is outside min-{} and max-{}",
+                    count, class.name, class.name, class.name
+                ));
             }
         }
+        Ok(())
+    }
 
-        // Check length constraints
-        if self.min_length > self.max_length {
-            return Err("min_length cannot be greater than max_length".to_string());
-        }
+    /// The shortest length from `min_length` to `max_length` at which a
+    /// uniformly chosen password carries at least `bits` of entropy
+    ///
+    /// Lengths beyond `UNIFORM_MAX_LENGTH` are not considered.
+    pub fn length_for_entropy(&self, bits: f64) -> Result<usize, String> {
+        self.validate()?;
 
-        // Check that minimum requirements can be satisfied
-        let total_min = self.min_numeric.unwrap_or(0)
-            + self.min_lower.unwrap_or(0)
-            + self.min_upper.unwrap_or(0)
-            + self.min_symbol.unwrap_or(0);
+        // No length beats the full alphabet at every position
+        let alphabet: usize = disjoint(&self.char_classes())
+            .iter()
+            .filter(|class| class.max != Some(0))
+            .map(|class| class.chars.len())
+            .sum();
+        let shortest = match alphabet {
+            0 | 1 => self.max_length,
+            _ => (bits / (alphabet as f64).log2()).ceil().max(0.0) as usize,
+        };
 
-        if total_min > self.max_length {
+        if self.min_length > UNIFORM_MAX_LENGTH {
             return Err(format!(
-                "Sum of minimum character requirements ({}) exceeds maximum length ({})",
-                total_min, self.max_length
+                "entropy targets are measured on passwords of at most {} characters; lower min-length to {}",
+                UNIFORM_MAX_LENGTH, UNIFORM_MAX_LENGTH
             ));
         }
+        let longest = self.max_length.min(UNIFORM_MAX_LENGTH);
+        for length in shortest.max(self.min_length)..=longest {
+            let mut constraints = self.clone();
+            constraints.min_length = length;
+            constraints.max_length = length;
+            if let Ok(generator) = PasswordGenerator::uniform(constraints)
+                && generator.keyspace().log2() >= bits
+            {
+                return Ok(length);
+            }
+        }
 
-        if total_min > self.min_length {
-            return Err(format!(
-                "Sum of minimum character requirements ({}) exceeds minimum length ({})",
-                total_min, self.min_length
+        Err(format!(
+            "No password of {} to {} characters reaches {} bits of entropy with these constraints",
+            self.min_length, longest, bits
+        ))
+    }
+
+    /// The character classes these constraints draw from, filtered
+    pub(crate) fn char_classes(&self) -> Vec<CharClass> {
+        let filter_ambiguous = |s: &str| filter_ambiguous(s, self.exclude_ambiguous);
+
+        let mut classes = vec![
+            CharClass::new(
+                "numeric",
+                filter_ambiguous(NUMERIC),
+                self.min_numeric,
+                self.max_numeric,
+            ),
+            CharClass::new(
+                "lower",
+                filter_ambiguous(LOWERCASE),
+                self.min_lower,
+                self.max_lower,
+            ),
+            CharClass::new(
+                "upper",
+                filter_ambiguous(UPPERCASE),
+                self.min_upper,
+                self.max_upper,
+            ),
+            CharClass::new(
+                "symbol",
+                filter_ambiguous(&self.symbols),
+                self.min_symbol,
+                self.max_symbol,
+            ),
+        ];
+
+        // Kana are only used when asked for
+        if self.min_hiragana.is_some() || self.max_hiragana.is_some() {
+            classes.push(CharClass::new(
+                "hiragana",
+                filter_ambiguous(HIRAGANA),
+                self.min_hiragana,
+                self.max_hiragana,
+            ));
+        }
+        if self.min_katakana.is_some() || self.max_katakana.is_some() {
+            classes.push(CharClass::new(
+                "katakana",
+                filter_ambiguous(KATAKANA),
+                self.min_katakana,
+                self.max_katakana,
             ));
         }
 
-        Ok(())
+        classes
+    }
+}
+
+/// One class of characters together with its count limits
+#[derive(Debug, Clone)]
+pub(crate) struct CharClass {
+    /// Name used in option names, e.g. "numeric" for `--min-numeric`
+    pub(crate) name: &'static str,
+    pub(crate) chars: Vec<char>,
+    pub(crate) min: usize,
+    pub(crate) max: Option<usize>,
+    /// Widest UTF-8 encoding of a character in the class, in bytes
+    pub(crate) width: usize,
+}
+
+impl CharClass {
+    fn new(name: &'static str, chars: Vec<char>, min: Option<usize>, max: Option<usize>) -> Self {
+        let width = chars.iter().map(|c| c.len_utf8()).max().unwrap_or(1);
+        Self {
+            name,
+            chars,
+            min: min.unwrap_or(0),
+            max,
+            width,
+        }
+    }
+
+    /// Whether the class can take another character beyond `count`
+    fn has_room(&self, count: usize) -> bool {
+        self.max.is_none_or(|max| count < max)
+    }
+}
+
+/// Exact number of passwords for every length and class composition
+///
+/// Built one class at a time: `tables[k]` maps a length and a byte
+/// overhead to the number of strings using only the first `k` classes.
+/// Adding `c` characters of a class to a string of length `n` multiplies
+/// by `C(n + c, c)` ways to place them and `size^c` ways to pick them.
+#[derive(Debug, Clone)]
+struct Compositions {
+    tables: Vec<BTreeMap<(usize, usize), BigUint>>,
+    min_length: usize,
+    /// Bytes beyond one per character, per class; all zero without a byte limit
+    overheads: Vec<usize>,
+}
+
+impl Compositions {
+    fn new(classes: &[CharClass], constraints: &PasswordConstraints) -> Self {
+        let max_length = constraints.max_length;
+        let overheads: Vec<usize> = classes
+            .iter()
+            .map(|class| match constraints.max_bytes {
+                Some(_) => class.width - 1,
+                None => 0,
+            })
+            .collect();
+        let fits = |length: usize, overhead: usize| {
+            constraints
+                .max_bytes
+                .is_none_or(|max| length + overhead <= max)
+        };
+
+        let mut tables = vec![BTreeMap::from([((0, 0), BigUint::one())])];
+        for (class, &overhead) in classes.iter().zip(&overheads) {
+            let size = class.chars.len() as u32;
+            let mut table = BTreeMap::new();
+            for (&(length, bytes), ways) in tables.last().expect("starts non-empty") {
+                let most = class
+                    .max
+                    .unwrap_or(max_length)
+                    .min(max_length.saturating_sub(length));
+                // `ways * C(length + count, count) * size^count`, updated one
+                // count at a time with small factors only
+                let mut added = ways.clone();
+                for count in 0..=most {
+                    let key = (length + count, bytes + count * overhead);
+                    if count > 0 {
+                        added = added
+                            .mul_small(key.0 as u32)
+                            .div_rem_small(count as u32)
+                            .0
+                            .mul_small(size);
+                    }
+                    if !fits(key.0, key.1) || added.is_zero() {
+                        break;
+                    }
+                    if count >= class.min {
+                        let entry: &mut BigUint = table.entry(key).or_default();
+                        *entry = &*entry + &added;
+                    }
+                }
+            }
+            tables.push(table);
+        }
+
+        Self {
+            tables,
+            min_length: constraints.min_length,
+            overheads,
+        }
+    }
+
+    /// Complete passwords: long enough, by length and byte overhead
+    fn finals(&self) -> impl Iterator<Item = (&(usize, usize), &BigUint)> {
+        let last = self.tables.last().expect("starts non-empty");
+        last.range((self.min_length, 0)..)
+    }
+
+    /// Number of passwords satisfying the constraints
+    fn total(&self) -> BigUint {
+        self.finals()
+            .fold(BigUint::zero(), |total, (_, ways)| &total + ways)
+    }
+
+    /// Draw class counts with probability proportional to how many
+    /// passwords have them
+    fn sample(&self, classes: &[CharClass], rng: &mut dyn RandomSource) -> io::Result<Vec<usize>> {
+        let (keys, weights): (Vec<_>, Vec<_>) = self
+            .finals()
+            .map(|(&key, ways)| (key, ways.clone()))
+            .unzip();
+        let (mut length, mut bytes) = keys[BigUint::random_weighted(&weights, rng)?];
+
+        // Walk back through the classes, choosing how many each contributed
+        let mut counts = vec![0; classes.len()];
+        for k in (0..classes.len()).rev() {
+            let size = classes[k].chars.len() as u32;
+            // `C(length, count) * size^count`, updated one count at a time
+            let mut factor = BigUint::one();
+            let (options, weights): (Vec<_>, Vec<_>) = (0..=length)
+                .filter_map(|count| {
+                    if count > 0 {
+                        factor = factor
+                            .mul_small((length - count + 1) as u32)
+                            .div_rem_small(count as u32)
+                            .0
+                            .mul_small(size);
+                    }
+                    let before = (
+                        length - count,
+                        bytes.checked_sub(count * self.overheads[k])?,
+                    );
+                    let ways = self.tables[k].get(&before)?;
+                    let valid =
+                        count >= classes[k].min && classes[k].max.is_none_or(|max| count <= max);
+                    let weight = ways * &factor;
+                    (valid && !weight.is_zero()).then_some((count, weight))
+                })
+                .unzip();
+
+            let count = options[BigUint::random_weighted(&weights, rng)?];
+            counts[k] = count;
+            length -= count;
+            bytes -= count * self.overheads[k];
+        }
+        Ok(counts)
+    }
+}
+
+/// Classes with repeated characters removed, keeping each character in
+/// the first class that has it
+///
+/// Overlapping classes would count some passwords twice.
+fn disjoint(classes: &[CharClass]) -> Vec<CharClass> {
+    let mut seen = Vec::new();
+    let mut classes = classes.to_vec();
+    for class in &mut classes {
+        class.chars.retain(|&c| {
+            let new = !seen.contains(&c);
+            seen.push(c);
+            new
+        });
+    }
+    classes
+}
+
+/// Exact size of a constraint set's password space
+#[derive(Debug, Clone)]
+pub struct EntropyReport {
+    /// Usable classes and their number of distinct characters
+    pub classes: Vec<(&'static str, usize)>,
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Number of distinct passwords satisfying the constraints
+    pub keyspace: BigUint,
+}
+
+impl EntropyReport {
+    /// Number of distinct characters across all usable classes
+    pub fn alphabet(&self) -> usize {
+        self.classes.iter().map(|(_, size)| size).sum()
+    }
+
+    /// Entropy of a uniformly chosen password, in bits
+    pub fn bits(&self) -> f64 {
+        self.keyspace.log2()
+    }
+
+    /// The usual `length * log2(alphabet)` estimate at the maximum length
+    ///
+    /// This ignores the per-class limits and the shorter lengths.
+    pub fn naive_bits(&self) -> f64 {
+        self.max_length as f64 * (self.alphabet() as f64).log2()
     }
 }
 
 /// Password generator
 pub struct PasswordGenerator {
     constraints: PasswordConstraints,
-    lowercase_chars: Vec<char>,
-    uppercase_chars: Vec<char>,
-    numeric_chars: Vec<char>,
-    symbol_chars: Vec<char>,
+    classes: Vec<CharClass>,
+    /// Class indices ordered from the narrowest to the widest characters
+    by_width: Vec<usize>,
+    /// Lengths that can be generated within the class and byte limits
+    lengths: Vec<usize>,
+    /// Set when sampling uniformly over all valid passwords
+    uniform: Option<Compositions>,
 }
 
 impl PasswordGenerator {
@@ -94,66 +486,154 @@ impl PasswordGenerator {
     pub fn new(constraints: PasswordConstraints) -> Result<Self, String> {
         constraints.validate()?;
 
-        let filter_ambiguous = |s: &str| -> Vec<char> {
-            if constraints.exclude_ambiguous {
-                s.chars()
-                    .filter(|c| !AMBIGUOUS_CHARS.contains(c))
-                    .collect()
-            } else {
-                s.chars().collect()
+        let mut classes = constraints.char_classes();
+
+        // A class with no characters can't take any, even without a maximum
+        for class in &mut classes {
+            if class.chars.is_empty() {
+                class.max = Some(0);
             }
-        };
+        }
 
-        let lowercase_chars = filter_ambiguous(LOWERCASE);
-        let uppercase_chars = filter_ambiguous(UPPERCASE);
-        let numeric_chars = filter_ambiguous(NUMERIC);
-        let symbol_chars = filter_ambiguous(&constraints.symbols);
+        let mut by_width: Vec<usize> = (0..classes.len()).collect();
+        by_width.sort_by_key(|&i| classes[i].width);
 
-        Ok(Self {
+        let mut generator = Self {
             constraints,
-            lowercase_chars,
-            uppercase_chars,
-            numeric_chars,
-            symbol_chars,
+            classes,
+            by_width,
+            lengths: Vec::new(),
+            uniform: None,
+        };
+
+        generator.lengths = (generator.constraints.min_length..=generator.constraints.max_length)
+            .filter(|&length| generator.fits_length(length))
+            .collect();
+
+        if generator.lengths.is_empty() {
+            return Err(match generator.constraints.max_bytes {
+                Some(max_bytes) => format!(
+                    "No password of {} to {} characters fits in {} bytes with the minimum character requirements",
+                    generator.constraints.min_length, generator.constraints.max_length, max_bytes
+                ),
+                None => "No password satisfies the constraints".to_string(),
+            });
+        }
+
+        Ok(generator)
+    }
+
+    /// Create a generator that picks uniformly among all passwords
+    /// satisfying the constraints
+    ///
+    /// `new` spreads the free slots evenly across the classes, so a small
+    /// class such as digits gets as many characters as the letters. Here
+    /// each length and class composition is weighted by how many passwords
+    /// have it, which also favors the longest allowed lengths. Characters
+    /// that appear in more than one class only count towards the first.
+    pub fn uniform(constraints: PasswordConstraints) -> Result<Self, String> {
+        if constraints.max_length > UNIFORM_MAX_LENGTH {
+            return Err(format!(
+                "uniform sampling counts every password, which is limited to {} characters, not {}; lower max-length to {}",
+                UNIFORM_MAX_LENGTH, constraints.max_length, UNIFORM_MAX_LENGTH
+            ));
+        }
+        let mut generator = Self::new(constraints)?;
+        generator.classes = disjoint(&generator.classes);
+
+        let compositions = Compositions::new(&generator.classes, &generator.constraints);
+        if compositions.total().is_zero() {
+            return Err("No password satisfies the constraints".to_string());
+        }
+        generator.uniform = Some(compositions);
+        Ok(generator)
+    }
+
+    /// Number of distinct passwords satisfying the constraints
+    pub fn keyspace(&self) -> BigUint {
+        match self.uniform {
+            Some(ref compositions) => compositions.total(),
+            None => Compositions::new(&disjoint(&self.classes), &self.constraints).total(),
+        }
+    }
+
+    /// Exact keyspace of the constraints next to the naive estimate
+    ///
+    /// Fails for lengths beyond `UNIFORM_MAX_LENGTH`, which take too long
+    /// to count.
+    pub fn entropy_report(&self) -> Result<EntropyReport, String> {
+        if self.constraints.max_length > UNIFORM_MAX_LENGTH {
+            return Err(format!(
+                "the entropy report counts every password, which is limited to {} characters, not {}; lower max-length to {}",
+                UNIFORM_MAX_LENGTH, self.constraints.max_length, UNIFORM_MAX_LENGTH
+            ));
+        }
+        let classes = disjoint(&self.classes)
+            .into_iter()
+            .filter(|class| class.max != Some(0) && !class.chars.is_empty())
+            .map(|class| (class.name, class.chars.len()))
+            .collect();
+
+        Ok(EntropyReport {
+            classes,
+            min_length: self.constraints.min_length,
+            max_length: self.constraints.max_length,
+            keyspace: self.keyspace(),
         })
     }
 
     /// Generate a password satisfying the constraints
     pub fn generate(&self) -> io::Result<String> {
-        // Determine actual password length
-        let length = if self.constraints.min_length == self.constraints.max_length {
-            self.constraints.min_length
-        } else {
-            let range = self.constraints.max_length - self.constraints.min_length + 1;
-            self.constraints.min_length + SecureRandom::random_range(range)?
+        self.generate_with(&mut SecureRandom)
+    }
+
+    /// Generate a password using the given random source
+    pub fn generate_with(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        let counts = match self.uniform {
+            Some(ref compositions) => compositions.sample(&self.classes, rng)?,
+            None => self.spread_counts(rng)?,
         };
 
-        // Start with minimum counts for each type
-        let mut numeric_count = self.constraints.min_numeric.unwrap_or(0);
-        let mut lower_count = self.constraints.min_lower.unwrap_or(0);
-        let mut upper_count = self.constraints.min_upper.unwrap_or(0);
-        let mut symbol_count = self.constraints.min_symbol.unwrap_or(0);
+        // Build password
+        let mut password = Vec::new();
+
+        // Add characters according to final counts
+        for (class, &count) in self.classes.iter().zip(&counts) {
+            for _ in 0..count {
+                password.push(self.pick_random(&class.chars, rng)?);
+            }
+        }
+
+        // Shuffle to avoid predictable patterns
+        rng.shuffle(&mut password)?;
+
+        Ok(password.into_iter().collect())
+    }
+
+    /// Class counts with the free slots handed to random classes one at a time
+    fn spread_counts(&self, rng: &mut dyn RandomSource) -> io::Result<Vec<usize>> {
+        // Determine actual password length
+        let length = self.lengths[rng.random_range(self.lengths.len())?];
+
+        // Start with minimum counts for each class
+        let mut counts: Vec<usize> = self.classes.iter().map(|class| class.min).collect();
+        let mut bytes = self.bytes_for(&counts);
 
         // Distribute remaining slots
-        let total = numeric_count + lower_count + upper_count + symbol_count;
-        let mut remaining = length - total;
+        let total: usize = counts.iter().sum();
+        let mut remaining = length.checked_sub(total).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Minimum character requirements exceed the password length",
+            )
+        })?;
 
         while remaining > 0 {
-            // Build a list of character types that can still accept more characters
-            let mut available_types = Vec::new();
-
-            if self.constraints.max_numeric.map_or(true, |max| numeric_count < max) {
-                available_types.push(0);
-            }
-            if self.constraints.max_lower.map_or(true, |max| lower_count < max) {
-                available_types.push(1);
-            }
-            if self.constraints.max_upper.map_or(true, |max| upper_count < max) {
-                available_types.push(2);
-            }
-            if self.constraints.max_symbol.map_or(true, |max| symbol_count < max) {
-                available_types.push(3);
-            }
+            // Build a list of classes that can still accept more characters
+            let available_types: Vec<usize> = (0..self.classes.len())
+                .filter(|&i| self.classes[i].has_room(counts[i]))
+                .filter(|&i| self.fits_after(&counts, i, bytes, remaining))
+                .collect();
 
             if available_types.is_empty() {
                 return Err(io::Error::new(
@@ -162,51 +642,77 @@ impl PasswordGenerator {
                 ));
             }
 
-            // Pick a random available type and increment its count
-            let type_index = SecureRandom::random_range(available_types.len())?;
-            match available_types[type_index] {
-                0 => numeric_count += 1,
-                1 => lower_count += 1,
-                2 => upper_count += 1,
-                3 => symbol_count += 1,
-                _ => unreachable!(),
-            }
+            // Pick a random available class and increment its count
+            let class = available_types[rng.random_range(available_types.len())?];
+            counts[class] += 1;
+            bytes += self.classes[class].width;
 
             remaining -= 1;
         }
 
-        // Build password
-        let mut password = Vec::new();
+        Ok(counts)
+    }
 
-        // Add characters according to final counts
-        for _ in 0..numeric_count {
-            password.push(self.pick_random(&self.numeric_chars)?);
-        }
-        for _ in 0..lower_count {
-            password.push(self.pick_random(&self.lowercase_chars)?);
-        }
-        for _ in 0..upper_count {
-            password.push(self.pick_random(&self.uppercase_chars)?);
-        }
-        for _ in 0..symbol_count {
-            password.push(self.pick_random(&self.symbol_chars)?);
+    /// Upper bound on the UTF-8 size of a password with these class counts
+    fn bytes_for(&self, counts: &[usize]) -> usize {
+        self.classes
+            .iter()
+            .zip(counts)
+            .map(|(class, count)| class.width * count)
+            .sum()
+    }
+
+    /// Fewest bytes needed to add `slots` more characters, or None if the
+    /// class maximums leave no room for them
+    fn min_fill_bytes(&self, counts: &[usize], mut slots: usize) -> Option<usize> {
+        let mut bytes = 0;
+        for &i in &self.by_width {
+            let room = self.classes[i]
+                .max
+                .map_or(slots, |max| max.saturating_sub(counts[i]).min(slots));
+            bytes += room * self.classes[i].width;
+            slots -= room;
         }
+        (slots == 0).then_some(bytes)
+    }
 
-        // Shuffle to avoid predictable patterns
-        SecureRandom::shuffle(&mut password)?;
+    /// Whether the class maximums leave room for a password of this length
+    /// within the byte limit
+    fn fits_length(&self, length: usize) -> bool {
+        let mins: Vec<usize> = self.classes.iter().map(|class| class.min).collect();
+        let total: usize = mins.iter().sum();
+        length
+            .checked_sub(total)
+            .and_then(|slots| self.min_fill_bytes(&mins, slots))
+            .is_some_and(|fill| {
+                self.constraints
+                    .max_bytes
+                    .is_none_or(|max_bytes| self.bytes_for(&mins) + fill <= max_bytes)
+            })
+    }
 
-        Ok(password.into_iter().collect())
+    /// Whether adding one character of `class` still leaves a way to fill
+    /// the other remaining slots within the byte limit
+    fn fits_after(&self, counts: &[usize], class: usize, bytes: usize, remaining: usize) -> bool {
+        let Some(max_bytes) = self.constraints.max_bytes else {
+            return true;
+        };
+
+        let mut counts = counts.to_vec();
+        counts[class] += 1;
+        self.min_fill_bytes(&counts, remaining - 1)
+            .is_some_and(|fill| bytes + self.classes[class].width + fill <= max_bytes)
     }
 
     /// Pick a random character from a character set
-    fn pick_random(&self, chars: &[char]) -> io::Result<char> {
+    fn pick_random(&self, chars: &[char], rng: &mut dyn RandomSource) -> io::Result<char> {
         if chars.is_empty() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Character set is empty",
             ));
         }
-        let index = SecureRandom::random_range(chars.len())?;
+        let index = rng.random_range(chars.len())?;
         Ok(chars[index])
     }
 }
@@ -214,6 +720,8 @@ impl PasswordGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::random::SeededRandom;
+    use crate::stats;
 
     #[test]
     fn test_basic_password_generation() {
@@ -226,8 +734,13 @@ mod tests {
             max_upper: None,
             min_symbol: None,
             max_symbol: None,
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
             min_length: 16,
             max_length: 16,
+            max_bytes: None,
             symbols: "!@#$%^&*()_+-=[]{}|;:,.<>?".to_string(),
             exclude_ambiguous: false,
         };
@@ -249,8 +762,13 @@ mod tests {
             max_upper: Some(4),
             min_symbol: Some(2),
             max_symbol: Some(4),
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
             min_length: 16,
             max_length: 16,
+            max_bytes: None,
             symbols: "!@#$".to_string(),
             exclude_ambiguous: false,
         };
@@ -266,10 +784,16 @@ mod tests {
         let upper_count = password.chars().filter(|c| c.is_uppercase()).count();
         let symbol_count = password.chars().filter(|c| "!@#$".contains(*c)).count();
 
-        assert!(numeric_count >= 2 && numeric_count <= 4);
-        assert!(lower_count >= 2 && lower_count <= 4);
-        assert!(upper_count >= 2 && upper_count <= 4);
-        assert!(symbol_count >= 2 && symbol_count <= 4);
+        assert!((2..=4).contains(&numeric_count));
+        assert!((2..=4).contains(&lower_count));
+        assert!((2..=4).contains(&upper_count));
+        assert!((2..=4).contains(&symbol_count));
+    }
+
+    #[test]
+    fn test_unique_chars() {
+        assert_eq!(unique_chars("a!b!a".chars().collect()), vec!['a', '!', 'b']);
+        assert!(unique_chars(Vec::new()).is_empty());
     }
 
     #[test]
@@ -283,8 +807,13 @@ mod tests {
             max_upper: Some(5),
             min_symbol: None,
             max_symbol: None,
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
             min_length: 15,
             max_length: 15,
+            max_bytes: None,
             symbols: "!@#$".to_string(),
             exclude_ambiguous: true,
         };
@@ -309,8 +838,13 @@ mod tests {
             max_upper: None,
             min_symbol: None,
             max_symbol: None,
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
             min_length: 16,
             max_length: 16,
+            max_bytes: None,
             symbols: "!@#$".to_string(),
             exclude_ambiguous: false,
         };
@@ -329,12 +863,492 @@ mod tests {
             max_upper: None,
             min_symbol: None,
             max_symbol: None,
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
             min_length: 15,
             max_length: 15,
+            max_bytes: None,
             symbols: "!@#$".to_string(),
             exclude_ambiguous: false,
         };
 
         assert!(constraints.validate().is_err());
     }
+
+    fn kana_constraints() -> PasswordConstraints {
+        PasswordConstraints {
+            min_numeric: None,
+            max_numeric: None,
+            min_lower: None,
+            max_lower: None,
+            min_upper: None,
+            max_upper: None,
+            min_symbol: None,
+            max_symbol: None,
+            min_hiragana: Some(3),
+            max_hiragana: Some(3),
+            min_katakana: Some(2),
+            max_katakana: Some(2),
+            min_length: 10,
+            max_length: 10,
+            max_bytes: None,
+            symbols: "!@#$".to_string(),
+            exclude_ambiguous: false,
+        }
+    }
+
+    #[test]
+    fn test_empty_symbol_set() {
+        // Without a minimum, an empty or fully ambiguous symbol set is skipped
+        for (symbols, exclude_ambiguous) in [("", false), ("|", true)] {
+            let mut constraints = kana_constraints();
+            constraints.min_hiragana = None;
+            constraints.max_hiragana = None;
+            constraints.min_katakana = None;
+            constraints.max_katakana = None;
+            constraints.symbols = symbols.to_string();
+            constraints.exclude_ambiguous = exclude_ambiguous;
+
+            let generator = PasswordGenerator::new(constraints.clone()).unwrap();
+            for _ in 0..20 {
+                let password = generator.generate().unwrap();
+                assert!(password.chars().all(|c| c.is_ascii_alphanumeric()));
+            }
+
+            constraints.min_symbol = Some(1);
+            assert!(PasswordGenerator::new(constraints).is_err());
+        }
+    }
+
+    #[test]
+    fn test_kana_classes() {
+        let generator = PasswordGenerator::new(kana_constraints()).unwrap();
+        let password = generator.generate().unwrap();
+
+        assert_eq!(password.chars().count(), 10);
+        assert_eq!(
+            password.chars().filter(|c| HIRAGANA.contains(*c)).count(),
+            3
+        );
+        assert_eq!(
+            password.chars().filter(|c| KATAKANA.contains(*c)).count(),
+            2
+        );
+
+        // Precomposed only: no combining voicing marks, so the output is NFC
+        assert!(!password.contains(['\u{3099}', '\u{309A}']));
+    }
+
+    #[test]
+    fn test_kana_sets_are_precomposed() {
+        assert_eq!(HIRAGANA.chars().count(), 71);
+        assert_eq!(KATAKANA.chars().count(), 71);
+        for (h, k) in HIRAGANA.chars().zip(KATAKANA.chars()) {
+            assert!(('\u{3041}'..='\u{3096}').contains(&h));
+            // Katakana sit 0x60 code points after their hiragana
+            assert_eq!(k as u32, h as u32 + 0x60);
+        }
+    }
+
+    #[test]
+    fn test_kana_off_by_default() {
+        let mut constraints = kana_constraints();
+        constraints.min_hiragana = None;
+        constraints.max_hiragana = None;
+        constraints.min_katakana = None;
+        constraints.max_katakana = None;
+
+        let generator = PasswordGenerator::new(constraints).unwrap();
+        assert!(generator.generate().unwrap().is_ascii());
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        // Four kana (12 bytes) leave room for four ASCII characters
+        let mut constraints = kana_constraints();
+        constraints.min_hiragana = Some(4);
+        constraints.max_hiragana = None;
+        constraints.min_katakana = None;
+        constraints.max_katakana = None;
+        constraints.min_length = 6;
+        constraints.max_length = 12;
+        constraints.max_bytes = Some(16);
+
+        let generator = PasswordGenerator::new(constraints.clone()).unwrap();
+        for _ in 0..20 {
+            let password = generator.generate().unwrap();
+            assert!(password.len() <= 16, "{}", password);
+            assert!((6..=8).contains(&password.chars().count()));
+        }
+
+        constraints.max_bytes = Some(11);
+        assert!(PasswordGenerator::new(constraints).is_err());
+    }
+
+    /// Digits and lowercase letters only
+    fn digits_and_lower(min_length: usize, max_length: usize) -> PasswordConstraints {
+        PasswordConstraints {
+            min_numeric: None,
+            max_numeric: None,
+            min_lower: None,
+            max_lower: None,
+            min_upper: None,
+            max_upper: Some(0),
+            min_symbol: None,
+            max_symbol: Some(0),
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
+            min_length,
+            max_length,
+            max_bytes: None,
+            symbols: "!@#$".to_string(),
+            exclude_ambiguous: false,
+        }
+    }
+
+    #[test]
+    fn test_golden_passwords() {
+        // Changing these means seeded bug reports no longer reproduce
+        let mut constraints = digits_and_lower(12, 12);
+        constraints.min_numeric = Some(2);
+        let generator = PasswordGenerator::new(constraints.clone()).unwrap();
+        let mut rng = SeededRandom::new(1);
+        assert_eq!(generator.generate_with(&mut rng).unwrap(), "21tlw50j1207");
+        assert_eq!(generator.generate_with(&mut rng).unwrap(), "81c8t8kbik2g");
+
+        let generator = PasswordGenerator::uniform(constraints).unwrap();
+        let mut rng = SeededRandom::new(1);
+        assert_eq!(generator.generate_with(&mut rng).unwrap(), "t522y0khtfjw");
+    }
+
+    #[test]
+    fn test_characters_are_uniform_within_each_class() {
+        let mut constraints = digits_and_lower(16, 16);
+        constraints.max_upper = None;
+        constraints.max_symbol = None;
+        constraints.min_symbol = Some(1);
+
+        for (seed, uniform) in [(5, false), (6, true)] {
+            let generator = if uniform {
+                PasswordGenerator::uniform(constraints.clone()).unwrap()
+            } else {
+                PasswordGenerator::new(constraints.clone()).unwrap()
+            };
+            let mut counts: Vec<Vec<u64>> = generator
+                .classes
+                .iter()
+                .map(|class| vec![0; class.chars.len()])
+                .collect();
+
+            let mut rng = SeededRandom::new(seed);
+            for _ in 0..20_000 {
+                for c in generator.generate_with(&mut rng).unwrap().chars() {
+                    for (class, counts) in generator.classes.iter().zip(&mut counts) {
+                        if let Some(i) = class.chars.iter().position(|&x| x == c) {
+                            counts[i] += 1;
+                        }
+                    }
+                }
+            }
+
+            for (class, counts) in generator.classes.iter().zip(&counts) {
+                if !counts.is_empty() {
+                    stats::assert_uniform(class.name, counts);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_classes_are_spread_over_positions() {
+        // Four digits in twelve characters: each position is a digit a third of the time
+        let mut constraints = digits_and_lower(12, 12);
+        constraints.min_numeric = Some(4);
+        constraints.max_numeric = Some(4);
+        let generator = PasswordGenerator::new(constraints).unwrap();
+
+        let mut digits = [0u64; 12];
+        let mut rng = SeededRandom::new(7);
+        let samples = 10_000;
+        for _ in 0..samples {
+            let password = generator.generate_with(&mut rng).unwrap();
+            for (position, c) in password.chars().enumerate() {
+                if c.is_ascii_digit() {
+                    digits[position] += 1;
+                }
+            }
+        }
+
+        for (position, &count) in digits.iter().enumerate() {
+            stats::assert_fits(
+                &format!("digits at position {}", position),
+                &[count, samples - count],
+                &[1.0 / 3.0, 2.0 / 3.0],
+            );
+        }
+    }
+
+    #[test]
+    fn test_uniform_keyspace() {
+        let generator = PasswordGenerator::uniform(digits_and_lower(2, 2)).unwrap();
+        assert_eq!(generator.keyspace().to_string(), "1296");
+
+        // Passwords with at least one digit: 36^2 - 26^2
+        let mut constraints = digits_and_lower(2, 2);
+        constraints.min_numeric = Some(1);
+        let generator = PasswordGenerator::uniform(constraints.clone()).unwrap();
+        assert_eq!(generator.keyspace().to_string(), "620");
+        for _ in 0..20 {
+            assert!(
+                generator
+                    .generate()
+                    .unwrap()
+                    .chars()
+                    .any(|c| c.is_ascii_digit())
+            );
+        }
+
+        // The same set is counted for the default generator
+        let generator = PasswordGenerator::new(constraints).unwrap();
+        assert_eq!(generator.keyspace().to_string(), "620");
+
+        let generator = PasswordGenerator::uniform(digits_and_lower(1, 2)).unwrap();
+        assert_eq!(generator.keyspace().to_string(), (36 + 1296).to_string());
+    }
+
+    #[test]
+    fn test_uniform_long_passwords() {
+        // Without limits every string over the 36 characters counts
+        let generator = PasswordGenerator::uniform(digits_and_lower(300, 300)).unwrap();
+        assert_eq!(generator.keyspace(), BigUint::from(36).pow(300));
+        assert_eq!(generator.generate().unwrap().len(), 300);
+
+        let e = PasswordGenerator::uniform(digits_and_lower(8, UNIFORM_MAX_LENGTH + 1))
+            .err()
+            .unwrap();
+        assert!(e.contains("lower max-length to 1024"), "{}", e);
+        assert!(
+            digits_and_lower(UNIFORM_MAX_LENGTH + 1, MAX_LENGTH)
+                .length_for_entropy(80.0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_entropy_report() {
+        let mut constraints = digits_and_lower(2, 2);
+        constraints.min_numeric = Some(1);
+        let report = PasswordGenerator::new(constraints)
+            .unwrap()
+            .entropy_report()
+            .unwrap();
+
+        assert_eq!(report.classes, [("numeric", 10), ("lower", 26)]);
+        assert_eq!(report.alphabet(), 36);
+        assert_eq!(report.keyspace.to_string(), "620");
+        assert!((report.bits() - 620f64.log2()).abs() < 1e-9);
+        assert!((report.naive_bits() - 2.0 * 36f64.log2()).abs() < 1e-9);
+
+        // Ambiguous characters and repeated symbols are not counted
+        let mut constraints = digits_and_lower(30, 30);
+        constraints.max_symbol = None;
+        constraints.symbols = "!!a|".to_string();
+        constraints.exclude_ambiguous = true;
+        let report = PasswordGenerator::new(constraints)
+            .unwrap()
+            .entropy_report()
+            .unwrap();
+        assert_eq!(
+            report.classes,
+            [("numeric", 8), ("lower", 25), ("symbol", 1)]
+        );
+        // Too big for a u128
+        assert_eq!(report.keyspace, BigUint::from(34).pow(30));
+        assert!(report.bits() > 128.0);
+
+        let generator =
+            PasswordGenerator::new(digits_and_lower(8, UNIFORM_MAX_LENGTH + 1)).unwrap();
+        assert!(generator.entropy_report().is_err());
+    }
+
+    #[test]
+    fn test_length_for_entropy() {
+        // 36^15 is about 2^77.55 and 36^16 about 2^82.72
+        let constraints = digits_and_lower(1, 64);
+        assert_eq!(constraints.length_for_entropy(80.0), Ok(16));
+        assert_eq!(constraints.length_for_entropy(77.0), Ok(15));
+        assert_eq!(constraints.length_for_entropy(0.0), Ok(1));
+
+        // Requiring a digit leaves 36^15 - 26^15, about 2^77.54, so it
+        // takes one more character
+        assert_eq!(constraints.length_for_entropy(77.545), Ok(15));
+        let mut constraints = digits_and_lower(1, 64);
+        constraints.min_numeric = Some(1);
+        assert_eq!(constraints.length_for_entropy(77.545), Ok(16));
+
+        let constraints = digits_and_lower(1, 10);
+        assert!(constraints.length_for_entropy(80.0).is_err());
+    }
+
+    #[test]
+    fn test_uniform_class_balance() {
+        // Uniform over 36 characters, so digits come up 10 times in 36
+        let generator = PasswordGenerator::uniform(digits_and_lower(1, 1)).unwrap();
+        let digits = (0..2000)
+            .filter(|_| {
+                generator
+                    .generate()
+                    .unwrap()
+                    .chars()
+                    .all(|c| c.is_ascii_digit())
+            })
+            .count();
+        assert!((400..=720).contains(&digits), "{} digits", digits);
+    }
+
+    #[test]
+    fn test_uniform_max_bytes() {
+        // Three digits, or one hiragana (3 bytes) and two digits
+        let mut constraints = digits_and_lower(3, 3);
+        constraints.max_lower = Some(0);
+        constraints.max_hiragana = Some(3);
+        constraints.max_bytes = Some(5);
+
+        let generator = PasswordGenerator::uniform(constraints).unwrap();
+        assert_eq!(
+            generator.keyspace().to_string(),
+            (1000 + 3 * 71 * 100).to_string()
+        );
+        for _ in 0..20 {
+            let password = generator.generate().unwrap();
+            assert!(password.len() <= 5, "{}", password);
+            assert_eq!(password.chars().count(), 3);
+        }
+    }
+
+    #[test]
+    fn test_uniform_overlapping_symbols() {
+        let mut constraints = digits_and_lower(1, 1);
+        constraints.max_upper = None;
+        constraints.max_symbol = None;
+        constraints.symbols = "a!!".to_string();
+
+        // 'a' counts as a lowercase letter and '!' only once
+        let generator = PasswordGenerator::uniform(constraints.clone()).unwrap();
+        assert_eq!(generator.keyspace().to_string(), "63");
+
+        constraints.symbols = "a".to_string();
+        constraints.min_symbol = Some(1);
+        assert!(PasswordGenerator::uniform(constraints).is_err());
+    }
+
+    /// A count limit that is usually small, sometimes unset and now and
+    /// then absurdly large
+    fn arbitrary_limit(rng: &mut dyn RandomSource) -> Option<usize> {
+        match rng.random_range(16).unwrap() {
+            0..=5 => None,
+            6 => Some(usize::MAX),
+            _ => Some(rng.random_range(6).unwrap()),
+        }
+    }
+
+    /// A random and often contradictory constraint set whose symbols never
+    /// overlap the other classes
+    fn arbitrary_constraints(rng: &mut dyn RandomSource) -> PasswordConstraints {
+        let symbols = ["", "!", "|", "!@#$", "!@#$%^&*()_+-=[]{}|;:,.<>?"];
+        let length = |rng: &mut dyn RandomSource| match rng.random_range(32).unwrap() {
+            0 => usize::MAX,
+            1 => MAX_LENGTH + 1,
+            _ => rng.random_range(24).unwrap(),
+        };
+        let kana = rng.random_range(4).unwrap() == 0;
+
+        PasswordConstraints {
+            min_numeric: arbitrary_limit(rng),
+            max_numeric: arbitrary_limit(rng),
+            min_lower: arbitrary_limit(rng),
+            max_lower: arbitrary_limit(rng),
+            min_upper: arbitrary_limit(rng),
+            max_upper: arbitrary_limit(rng),
+            min_symbol: arbitrary_limit(rng),
+            max_symbol: arbitrary_limit(rng),
+            min_hiragana: arbitrary_limit(rng).filter(|_| kana),
+            max_hiragana: arbitrary_limit(rng).filter(|_| kana),
+            min_katakana: arbitrary_limit(rng).filter(|_| kana),
+            max_katakana: arbitrary_limit(rng).filter(|_| kana),
+            min_length: length(rng),
+            max_length: length(rng),
+            max_bytes: arbitrary_limit(rng).map(|bytes| bytes.saturating_mul(4)),
+            symbols: symbols[rng.random_range(symbols.len()).unwrap()].to_string(),
+            exclude_ambiguous: rng.random_range(2).unwrap() == 0,
+        }
+    }
+
+    #[test]
+    fn test_property_valid_constraints_generate_compliant_passwords() {
+        let mut rng = SeededRandom::new(22);
+        let mut generated = 0;
+        for _ in 0..5000 {
+            let constraints = arbitrary_constraints(&mut rng);
+            let valid = constraints.validate().is_ok();
+
+            for generator in [
+                PasswordGenerator::new(constraints.clone()),
+                PasswordGenerator::uniform(constraints.clone()),
+            ] {
+                // Validation is the only way a generator gets refused
+                let generator = match generator {
+                    Ok(generator) => generator,
+                    Err(e) => {
+                        assert!(!valid, "{:?} passed validation but: {}", constraints, e);
+                        continue;
+                    }
+                };
+                assert!(valid, "{:?} failed validation", constraints);
+
+                match generator.generate_with(&mut rng) {
+                    Ok(password) => {
+                        if let Err(e) = constraints.verify(&password) {
+                            panic!("{:?} generated {:?}: {}", constraints, password, e);
+                        }
+                        generated += 1;
+                    }
+                    Err(e) => panic!("{:?} failed to generate: {}", constraints, e),
+                }
+            }
+        }
+        // Make sure the property isn't vacuous
+        assert!(generated > 500, "only {} passwords generated", generated);
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut constraints = digits_and_lower(4, 6);
+        constraints.min_numeric = Some(1);
+        constraints.max_bytes = Some(5);
+        assert!(constraints.verify("ab1c").is_ok());
+        assert!(constraints.verify("abc").unwrap_err().contains("length"));
+        assert!(constraints.verify("abcd").unwrap_err().contains("numeric"));
+        assert!(constraints.verify("ab1~").unwrap_err().contains("'~'"));
+        assert!(constraints.verify("ab1cde").unwrap_err().contains("bytes"));
+    }
+
+    #[test]
+    fn test_romaji_syllables() {
+        let mut seen = std::collections::HashSet::new();
+        for syllable in ROMAJI_SYLLABLES {
+            assert!(seen.insert(syllable), "duplicate {}", syllable);
+            let vowels: Vec<usize> = syllable
+                .match_indices(['a', 'i', 'u', 'e', 'o'])
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(vowels, [syllable.len() - 1], "{}", syllable);
+        }
+        assert_eq!(ROMAJI_SYLLABLES.len(), 68);
+        assert_eq!(romaji_word(0, &mut SecureRandom).unwrap(), "");
+    }
 }
@@ -0,0 +1,162 @@
+use std::io;
+
+/// Repetition count cutoff: 1 + ceil(40 / H) with H = 8 bits per byte
+///
+/// The sources claim full entropy, and a false alarm rate of 2^-40 per
+/// byte keeps spurious failures out of even very large batches.
+pub const RCT_CUTOFF: usize = 6;
+
+/// Samples per adaptive proportion window for non-binary sources
+pub const APT_WINDOW: usize = 512;
+
+/// Adaptive proportion cutoff: 1 + CRITBINOM(512, 2^-8, 1 - 2^-40)
+pub const APT_CUTOFF: usize = 19;
+
+/// The continuous health tests of NIST SP 800-90B, section 4.4
+///
+/// Bytes are fed in as they are produced. Once a test trips, every later
+/// check fails too, so a broken source cannot quietly recover.
+#[derive(Debug, Clone)]
+pub struct HealthTests {
+    /// Repetition count test: the current run of equal bytes
+    rct_value: u8,
+    rct_count: usize,
+    /// Adaptive proportion test: the window's first byte, how often it
+    /// has appeared, and how many bytes of the window have been seen
+    apt_value: u8,
+    apt_count: usize,
+    apt_seen: usize,
+    failure: Option<String>,
+}
+
+impl Default for HealthTests {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthTests {
+    pub const fn new() -> Self {
+        HealthTests {
+            rct_value: 0,
+            rct_count: 0,
+            apt_value: 0,
+            apt_count: 0,
+            apt_seen: 0,
+            failure: None,
+        }
+    }
+
+    /// Run both tests over the next bytes from the source
+    ///
+    /// # Errors
+    /// Returns an error if either test trips, now or earlier
+    pub fn check(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.failure.is_none() {
+            for &byte in bytes {
+                if let Err(failure) = self.sample(byte) {
+                    self.failure = Some(failure);
+                    break;
+                }
+            }
+        }
+
+        match self.failure {
+            Some(ref failure) => Err(io::Error::other(format!(
+                "RNG health test failed ({}); refusing to generate",
+                failure
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The failure that tripped a test, if any
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    fn sample(&mut self, byte: u8) -> Result<(), String> {
+        // Repetition count test (4.4.1)
+        if self.rct_count > 0 && byte == self.rct_value {
+            self.rct_count += 1;
+            if self.rct_count >= RCT_CUTOFF {
+                return Err(format!(
+                    "repetition count test: byte {:#04x} repeated {} times",
+                    byte, self.rct_count
+                ));
+            }
+        } else {
+            self.rct_value = byte;
+            self.rct_count = 1;
+        }
+
+        // Adaptive proportion test (4.4.2)
+        if self.apt_seen == 0 {
+            self.apt_value = byte;
+            self.apt_count = 1;
+        } else if byte == self.apt_value {
+            self.apt_count += 1;
+            if self.apt_count >= APT_CUTOFF {
+                return Err(format!(
+                    "adaptive proportion test: byte {:#04x} appeared {} times in {} bytes",
+                    byte,
+                    self.apt_count,
+                    self.apt_seen + 1
+                ));
+            }
+        }
+        self.apt_seen = (self.apt_seen + 1) % APT_WINDOW;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{RandomSource, SeededRandom};
+
+    #[test]
+    fn test_random_bytes_pass() {
+        let mut bytes = vec![0u8; 1 << 20];
+        SeededRandom::new(1).fill_bytes(&mut bytes).unwrap();
+
+        let mut health = HealthTests::new();
+        health.check(&bytes).unwrap();
+        assert_eq!(health.failure(), None);
+    }
+
+    #[test]
+    fn test_repetition_count() {
+        let mut health = HealthTests::new();
+        health.check(&[7, 7, 7, 7, 7]).unwrap();
+        let e = health.check(&[7]).unwrap_err();
+        assert!(e.to_string().contains("repetition count"), "{}", e);
+
+        // The failure sticks
+        assert!(health.check(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_adaptive_proportion() {
+        // The first byte of the window keeps coming back, never in long runs
+        let mut bytes = Vec::new();
+        for i in 0..APT_CUTOFF as u8 {
+            bytes.extend([0, i + 1]);
+        }
+
+        let mut health = HealthTests::new();
+        health.check(&bytes[..bytes.len() - 2]).unwrap();
+        let e = health.check(&bytes[bytes.len() - 2..]).unwrap_err();
+        assert!(e.to_string().contains("adaptive proportion"), "{}", e);
+
+        // Spread over more than a window, the same bytes pass
+        let mut health = HealthTests::new();
+        let mut spread = Vec::new();
+        for i in 0..APT_CUTOFF {
+            spread.push(0);
+            spread.extend((0..40).map(|j| (1 + (i * 40 + j) % 255) as u8));
+        }
+        health.check(&spread).unwrap();
+    }
+}
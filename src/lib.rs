@@ -0,0 +1,26 @@
+//! genpass: a lightweight, flexible password generator
+//!
+//! The generators only depend on the standard library; `clap` is used for
+//! the command line interface.
+
+pub mod bigint;
+pub mod bip39;
+pub mod cli;
+pub mod clipboard;
+pub mod config;
+pub mod feasibility;
+pub mod generator;
+pub mod health;
+pub mod markov;
+pub mod mask;
+pub mod passphrase;
+pub mod pronounceable;
+pub mod random;
+pub mod regex;
+pub mod selftest;
+pub mod sha256;
+#[cfg(test)]
+mod stats;
+pub mod template;
+pub mod toml;
+pub mod wordlist;
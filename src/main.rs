@@ -1,105 +1,335 @@
-mod random;
-mod generator;
-mod config;
-mod clipboard;
-
 use clap::Parser;
-use generator::{PasswordConstraints, PasswordGenerator};
+use genpass::bip39;
+use genpass::cli::{Cli, Command, ConfigCommand};
+use genpass::generator::{self, EntropyReport, PasswordGenerator};
+use genpass::mask::{Mask, MaskOptions};
+use genpass::markov::{MarkovGenerator, MarkovModel};
+use genpass::passphrase::{DiceMode, PassphraseGenerator, PassphraseOptions};
+use genpass::pronounceable::PronounceableGenerator;
+use genpass::random::{ChaChaRng, RandomSource, SecureRandom, SeededRandom};
+use genpass::regex::RegexGenerator;
+use genpass::template::{Template, TemplateOptions};
+use genpass::wordlist::Wordlist;
+use genpass::{clipboard, config, selftest};
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
 use std::process;
 
-/// A lightweight, flexible password generator
-#[derive(Parser, Debug)]
-#[command(name = "genpass")]
-#[command(version, about, long_about = None)]
-pub struct Cli {
-    /// Minimum number of numeric characters (0-9)
-    #[arg(short = 'n', long)]
-    pub min_numeric: Option<usize>,
-
-    /// Maximum number of numeric characters (0-9)
-    #[arg(short = 'N', long)]
-    pub max_numeric: Option<usize>,
-
-    /// Minimum number of lowercase letters (a-z)
-    #[arg(short = 'a', long)]
-    pub min_lower: Option<usize>,
-
-    /// Maximum number of lowercase letters (a-z)
-    #[arg(short = 'A', long)]
-    pub max_lower: Option<usize>,
-
-    /// Minimum number of uppercase letters (A-Z)
-    #[arg(short = 'u', long)]
-    pub min_upper: Option<usize>,
-
-    /// Maximum number of uppercase letters (A-Z)
-    #[arg(short = 'U', long)]
-    pub max_upper: Option<usize>,
-
-    /// Minimum number of symbol characters
-    #[arg(short = 's', long)]
-    pub min_symbol: Option<usize>,
-
-    /// Maximum number of symbol characters
-    #[arg(short = 'S', long)]
-    pub max_symbol: Option<usize>,
-
-    /// Exact password length (shorthand for setting both min and max length)
-    #[arg(short = 'l', long, conflicts_with_all = ["min_length", "max_length"])]
-    pub length: Option<usize>,
-
-    /// Minimum total password length
-    #[arg(long)]
-    pub min_length: Option<usize>,
-
-    /// Maximum total password length
-    #[arg(long)]
-    pub max_length: Option<usize>,
-
-    /// Define which symbol characters to use
-    #[arg(long, default_value = "!@#$%^&*()_+-=[]{}|;:,.<>?")]
-    pub symbols: String,
-
-    /// Exclude visually ambiguous characters (0/O, 1/l/I, etc.)
-    #[arg(long)]
-    pub exclude_ambiguous: bool,
-
-    /// Number of passwords to generate
-    #[arg(long, short = 'c', default_value = "1")]
-    pub count: usize,
-
-    /// Copy the last generated password to clipboard (macOS only)
-    #[arg(short = 'C', long)]
-    pub copy: bool,
-
-    /// Load configuration from a named profile
-    #[arg(long)]
-    pub config: Option<String>,
-
-    /// Save current options to a named config (default: "default")
-    #[arg(long)]
-    pub save_config: Option<String>,
-
-    /// List all available saved configurations
-    #[arg(long)]
-    pub list_configs: bool,
-
-    /// Display current configuration settings
-    #[arg(long)]
-    pub status: Option<String>,
-
-    /// Interactive wizard mode for configuring password generation
-    #[arg(long)]
-    pub wizard: bool,
-
-    /// Set a named configuration as the default
-    #[arg(long)]
-    pub set_default: Option<String>,
+/// The generator selected by the merged configuration
+enum Generator {
+    Password(PasswordGenerator),
+    Passphrase(PassphraseGenerator),
+    Pronounceable(PronounceableGenerator),
+    Markov(MarkovGenerator),
+    Mask(Mask),
+    Template(Template),
+    Regex(RegexGenerator),
+    /// BIP39 mnemonic with the given number of words
+    Bip39(usize),
+}
+
+impl Generator {
+    fn generate(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        match self {
+            Generator::Password(generator) => generator.generate_with(rng),
+            Generator::Passphrase(generator) => generator.generate_with(rng),
+            Generator::Pronounceable(generator) => generator.generate_with(rng),
+            Generator::Mask(mask) => mask.generate_with(rng),
+            Generator::Template(template) => template.generate_with(rng),
+            Generator::Regex(generator) => generator.generate_with(rng),
+            Generator::Markov(generator) => generator.generate_with(rng),
+            Generator::Bip39(words) => bip39::generate_with(*words, rng),
+        }
+    }
+}
+
+/// Load a built-in wordlist by name or a custom one from a file,
+/// reporting its strength on stderr
+fn load_wordlist(path: &str) -> Wordlist {
+    let wordlist = match Wordlist::builtin(path) {
+        Some(Ok(wordlist)) => return wordlist,
+        Some(Err(e)) => Err(e),
+        None => Wordlist::load(path.as_ref()).map_err(|e| e.to_string()),
+    };
+    let wordlist = match wordlist {
+        Ok(wordlist) => wordlist,
+        Err(e) => {
+            eprintln!("Error loading wordlist: {}", e);
+            process::exit(1);
+        }
+    };
+
+    eprintln!(
+        "Wordlist: {} unique words, {:.2} bits per word",
+        wordlist.len(),
+        wordlist.entropy_per_word()
+    );
+    for warning in wordlist.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    wordlist
+}
+
+/// The mnemonic given to --verify, read from stdin for "-" so that it stays
+/// out of argv and the shell history
+fn read_mnemonic(source: &str) -> io::Result<String> {
+    if source != "-" {
+        return Ok(source.to_string());
+    }
+
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("Enter the mnemonic, then press Ctrl-D:");
+    }
+    let mut mnemonic = String::new();
+    stdin.read_to_string(&mut mnemonic)?;
+    Ok(mnemonic)
+}
+
+/// Read user-supplied entropy from a file or stdin and mix it into the RNG
+fn mix_extra_entropy(source: &Path) -> io::Result<()> {
+    let data = if source == Path::new("-") {
+        let mut stdin = io::stdin();
+        if stdin.is_terminal() {
+            eprintln!("Type random keys or coin flips, then press Ctrl-D:");
+        }
+        let mut data = Vec::new();
+        stdin.read_to_end(&mut data)?;
+        data
+    } else {
+        fs::read(source)?
+    };
+
+    if data.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no extra entropy was provided",
+        ));
+    }
+
+    SecureRandom::add_entropy(&data);
+    eprintln!("Mixed {} bytes of extra entropy into the RNG", data.len());
+    Ok(())
+}
+
+/// Train a Markov model on a text file and save it under a name
+fn train_model(name: &str, corpus: &Path, ngram: usize) -> io::Result<()> {
+    let text = fs::read_to_string(corpus)?;
+    let model = MarkovModel::train(&text, ngram)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    model.save(name)?;
+
+    println!(
+        "Trained {}-gram model '{}' ({} contexts)",
+        ngram,
+        name,
+        model.contexts()
+    );
+    println!(
+        "Model saved to {}",
+        MarkovModel::model_path(name).unwrap_or_default().display()
+    );
+    Ok(())
+}
+
+/// Print the exact keyspace of a constraint set next to the naive estimate
+fn print_entropy_report(report: &EntropyReport, uniform: bool) {
+    let classes: Vec<String> = report
+        .classes
+        .iter()
+        .map(|(name, size)| format!("{} {}", size, name))
+        .collect();
+
+    if report.min_length == report.max_length {
+        println!("Length:         {}", report.max_length);
+    } else {
+        println!("Length:         {} to {}", report.min_length, report.max_length);
+    }
+    println!(
+        "Alphabet:       {} characters ({})",
+        report.alphabet(),
+        classes.join(", ")
+    );
+    println!("Keyspace:       {}", report.keyspace);
+    println!("Entropy:        {:.2} bits", report.bits());
+    println!(
+        "Naive estimate: {:.2} bits ({} x log2 {})",
+        report.naive_bits(),
+        report.max_length,
+        report.alphabet()
+    );
+
+    let lost = report.naive_bits() - report.bits();
+    if lost > 0.005 {
+        println!(
+            "The constraints remove {:.2} bits compared to the naive estimate.",
+            lost
+        );
+    }
+    if !uniform {
+        println!(
+            "Note: without --uniform, generated passwords are not spread evenly over this keyspace and carry less entropy."
+        );
+    }
+}
+
+/// Print the entropy of whichever generator the options selected
+fn print_entropy(generator: &Generator, uniform: bool) -> Result<(), String> {
+    match generator {
+        Generator::Password(generator) => {
+            print_entropy_report(&generator.entropy_report()?, uniform)
+        }
+        Generator::Passphrase(generator) => {
+            println!("Words:          {}", generator.words());
+            println!(
+                "Entropy:        {:.2} bits ({:.2} per word)",
+                generator.entropy(),
+                generator.entropy_per_word()
+            );
+        }
+        Generator::Pronounceable(generator) => {
+            let entropy = generator.entropy();
+            println!(
+                "Entropy:        ~{:.2} bits on average, at least {:.2} bits",
+                entropy.average, entropy.minimum
+            );
+        }
+        Generator::Markov(generator) => {
+            println!(
+                "Entropy:        at least {:.2} bits (words are added until each password reaches --min-entropy)",
+                generator.min_entropy()
+            );
+        }
+        Generator::Mask(mask) => {
+            println!("Keyspace:       {}", mask.keyspace());
+            println!("Entropy:        {:.2} bits", mask.entropy());
+        }
+        Generator::Template(template) => {
+            println!("Entropy:        {:.2} bits", template.entropy());
+        }
+        Generator::Regex(generator) => {
+            let bits = generator.estimate_entropy().map_err(|e| e.to_string())?;
+            if generator.is_constrained() {
+                println!("Keyspace:       at most {}", generator.keyspace());
+                println!("Entropy:        ~{:.2} bits after lookaheads", bits);
+            } else {
+                println!("Keyspace:       {}", generator.keyspace());
+                println!("Entropy:        {:.2} bits", bits);
+            }
+        }
+        Generator::Bip39(words) => {
+            // Every word carries 11 bits, and one bit in 33 is checksum
+            println!("Words:          {}", words);
+            println!("Entropy:        {} bits", words * 11 * 32 / 33);
+        }
+    }
+    Ok(())
+}
+
+/// Load a profile, skipping bad lines with a warning only if asked to
+fn load_config(name: Option<&str>, lenient: bool) -> io::Result<config::Config> {
+    let config = if lenient {
+        let (config, warnings) = config::Config::load_lenient(name)?;
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        config
+    } else {
+        config::Config::load(name)?
+    };
+
+    if config::Config::needs_migration(name)? {
+        eprintln!(
+            "Hint: profile '{}' uses the old key=value format; run `genpass config migrate` to convert it to TOML",
+            name.unwrap_or("default")
+        );
+    }
+    Ok(config)
+}
+
+fn exit_with_config_error(e: &io::Error) -> ! {
+    if e.kind() == io::ErrorKind::InvalidData {
+        eprintln!("Error loading configuration:\n{}", e);
+        eprintln!("Fix these lines, or pass --lenient-config to skip them");
+    } else {
+        eprintln!("Error loading configuration: {}", e);
+    }
+    process::exit(1);
+}
+
+/// Print each self-test result and exit non-zero if any failed
+fn run_selftest() {
+    let checks = selftest::run();
+    for check in &checks {
+        match check.result {
+            Ok(()) => println!("ok    {}", check.name),
+            Err(ref e) => println!("FAIL  {}: {}", check.name, e),
+        }
+    }
+
+    let failed = checks.iter().filter(|check| check.result.is_err()).count();
+    if failed > 0 {
+        eprintln!("Error: {} of {} self-tests failed", failed, checks.len());
+        process::exit(1);
+    }
+    println!("All {} self-tests passed", checks.len());
+}
+
+/// Convert every legacy profile to TOML
+fn migrate_configs() {
+    let names = config::Config::list_configs().unwrap_or_else(|e| {
+        eprintln!("Error listing configurations: {}", e);
+        process::exit(1);
+    });
+
+    let mut failed = 0;
+    for name in &names {
+        match config::Config::migrate(name) {
+            Ok(Some(backup)) => println!("Migrated {} (backup: {})", name, backup.display()),
+            Ok(None) => println!("Skipped {} (already TOML or empty)", name),
+            Err(e) => {
+                eprintln!("Error migrating {}:\n{}", name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!(
+            "Error: {} of {} configurations could not be migrated",
+            failed,
+            names.len()
+        );
+        process::exit(1);
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    // Run a subcommand if one was given
+    if let Some(ref command) = cli.command {
+        match command {
+            Command::Train {
+                name,
+                corpus,
+                ngram,
+            } => {
+                if let Err(e) = train_model(name, corpus, *ngram) {
+                    eprintln!("Error training model: {}", e);
+                    process::exit(1);
+                }
+            }
+            Command::Selftest => run_selftest(),
+            Command::Config {
+                action: ConfigCommand::Migrate,
+            } => migrate_configs(),
+        }
+        return;
+    }
+
     // Run wizard mode if requested
     if cli.wizard {
         match config::Config::wizard() {
@@ -121,14 +351,14 @@ fn main() {
                     println!("Configuration saved to {}", path.display());
 
                     // Set as default if requested
-                    if set_as_default {
-                        if let Some(name_str) = name_to_save {
-                            if let Err(e) = config::Config::set_as_default(name_str) {
-                                eprintln!("Error setting as default: {}", e);
-                                process::exit(1);
-                            }
-                            println!("Set as default configuration");
+                    if set_as_default
+                        && let Some(name_str) = name_to_save
+                    {
+                        if let Err(e) = config::Config::set_as_default(name_str) {
+                            eprintln!("Error setting as default: {}", e);
+                            process::exit(1);
                         }
+                        println!("Set as default configuration");
                     }
                     println!();
                 }
@@ -151,8 +381,13 @@ fn main() {
                     max_upper: config.max_upper,
                     min_symbol: config.min_symbol,
                     max_symbol: config.max_symbol,
+                    min_hiragana: config.min_hiragana,
+                    max_hiragana: config.max_hiragana,
+                    min_katakana: config.min_katakana,
+                    max_katakana: config.max_katakana,
                     min_length,
                     max_length,
+                    max_bytes: config.max_bytes,
                     symbols: config.symbols.unwrap_or_else(|| "!@#$%^&*()_+-=[]{}|;:,.<>?".to_string()),
                     exclude_ambiguous: config.exclude_ambiguous.unwrap_or(false),
                 };
@@ -230,13 +465,35 @@ fn main() {
             Some(status_name.as_str())
         };
 
-        match config::Config::load(name) {
+        match load_config(name, cli.lenient_config) {
             Ok(config) => {
                 config.display(name);
                 return;
             }
+            Err(e) => exit_with_config_error(&e),
+        }
+    }
+
+    // Verify a BIP39 mnemonic if requested and exit
+    if let Some(ref source) = cli.verify {
+        let mnemonic = match read_mnemonic(source) {
+            Ok(mnemonic) => mnemonic,
             Err(e) => {
-                eprintln!("Error loading configuration: {}", e);
+                eprintln!("Error reading mnemonic: {}", e);
+                process::exit(1);
+            }
+        };
+        match bip39::mnemonic_to_entropy(&mnemonic) {
+            Ok(entropy) => {
+                println!(
+                    "Valid BIP39 mnemonic ({} words, {} bits of entropy)",
+                    mnemonic.split_whitespace().count(),
+                    entropy.len() * 8
+                );
+                return;
+            }
+            Err(e) => {
+                eprintln!("Invalid BIP39 mnemonic: {}", e);
                 process::exit(1);
             }
         }
@@ -244,8 +501,12 @@ fn main() {
 
     // Load saved configuration
     let config_name = cli.config.as_deref();
-    let mut config = match config::Config::load(config_name) {
+    let mut config = match load_config(config_name, cli.lenient_config) {
         Ok(cfg) => cfg,
+        // A broken default profile is as dangerous as a named one
+        Err(e) if config_name.is_some() || e.kind() == io::ErrorKind::InvalidData => {
+            exit_with_config_error(&e)
+        }
         Err(e) => {
             eprintln!("Warning: Could not load config: {}", e);
             config::Config::default()
@@ -275,33 +536,206 @@ fn main() {
         }
     }
 
-    // Determine password length constraints
-    let (min_length, max_length) = if let Some(length) = config.length {
-        (length, length)
+    // Mix in user-supplied entropy before anything is generated
+    if let Some(ref source) = cli.extra_entropy {
+        if source == Path::new("-") && config.dice.is_some_and(|d| d != DiceMode::Off) {
+            eprintln!("Error: --extra-entropy cannot read stdin while dice rolls are read from it");
+            process::exit(1);
+        }
+        if let Err(e) = mix_extra_entropy(source) {
+            eprintln!("Error reading extra entropy: {}", e);
+            process::exit(1);
+        }
+    }
+
+    let symbols = config
+        .symbols
+        .clone()
+        .unwrap_or_else(|| "!@#$%^&*()_+-=[]{}|;:,.<>?".to_string());
+
+    let generator = if let Some(words) = cli.bip39 {
+        // BIP39 mnemonic mode
+        if bip39::VALID_WORD_COUNTS.contains(&words) {
+            Ok(Generator::Bip39(words))
+        } else {
+            Err(format!(
+                "BIP39 mnemonics have 12, 15, 18, 21 or 24 words, not {}",
+                words
+            ))
+        }
+    } else if let Some(ref pattern) = config.regex {
+        // Regex mode: strings matching a validation pattern
+        RegexGenerator::new(pattern).and_then(|generator| {
+            let bits = generator.estimate_entropy().map_err(|e| e.to_string())?;
+            if generator.is_constrained() {
+                eprintln!(
+                    "Regex: ~{:.1} bits of entropy after lookaheads (keyspace at most {})",
+                    bits,
+                    generator.keyspace()
+                );
+            } else {
+                eprintln!(
+                    "Regex: keyspace {} ({:.1} bits of entropy)",
+                    generator.keyspace(),
+                    bits
+                );
+            }
+            Ok(Generator::Regex(generator))
+        })
+    } else if let Some(ref template) = config.template {
+        // Template mode: literal text and placeholders
+        let options = TemplateOptions {
+            mask: MaskOptions {
+                symbols,
+                exclude_ambiguous: config.exclude_ambiguous.unwrap_or(false),
+                charsets: config.charsets.clone(),
+            },
+            wordlist: match config.wordlist {
+                Some(ref path) if template.contains("{word") => load_wordlist(path),
+                _ => Wordlist::eff_short(),
+            },
+        };
+
+        Template::parse(template, options).map(|template| {
+            eprintln!("Template: {:.1} bits of entropy", template.entropy());
+            for warning in template.warnings() {
+                eprintln!("Warning: {}", warning);
+            }
+            Generator::Template(template)
+        })
+    } else if let Some(ref mask) = config.mask {
+        // Mask mode: one charset per position
+        let options = MaskOptions {
+            symbols,
+            exclude_ambiguous: config.exclude_ambiguous.unwrap_or(false),
+            charsets: config.charsets.clone(),
+        };
+
+        Mask::parse(mask, &options).map(|mask| {
+            eprintln!(
+                "Mask: {} positions, keyspace {} ({:.1} bits of entropy)",
+                mask.len(),
+                mask.keyspace(),
+                mask.entropy()
+            );
+            Generator::Mask(mask)
+        })
+    } else if let Some(words) = config
+        .words
+        .or(config.romaji.map(|_| if config.min_entropy.is_some() { 1 } else { 4 }))
+    {
+        // Passphrase mode (romaji passphrases default to four words, or as
+        // few as the entropy target allows)
+        let options = PassphraseOptions {
+            words,
+            separator: config.separator.clone().unwrap_or_else(|| "-".to_string()),
+            capitalization: config.capitalize.unwrap_or_default(),
+            add_digit: config.add_digit.unwrap_or(false),
+            add_symbol: config.add_symbol.unwrap_or(false),
+            symbols,
+            dice: config.dice.unwrap_or_default(),
+        };
+
+        let generator = if let Some(syllables) = config.romaji {
+            PassphraseGenerator::romaji(options, syllables).inspect(|generator| {
+                eprintln!(
+                    "Romaji: {} syllables per word, {:.2} bits per word",
+                    syllables,
+                    generator.entropy_per_word()
+                );
+            })
+        } else {
+            let wordlist = match config.wordlist {
+                Some(ref path) => load_wordlist(path),
+                None => Wordlist::eff_short(),
+            };
+
+            PassphraseGenerator::new(options, wordlist)
+        };
+
+        generator.map(|mut generator| {
+            // With a target, --words is the fewest words to use
+            if let Some(bits) = config.min_entropy {
+                generator.ensure_entropy(bits);
+                eprintln!(
+                    "Entropy target: {} bits, using {} words ({:.1} bits)",
+                    bits,
+                    generator.words(),
+                    generator.entropy()
+                );
+            }
+            Generator::Passphrase(generator)
+        })
+    } else if let Some(ref name) = config.markov {
+        // Markov mode: words from a trained model, at least min-length long
+        match MarkovModel::load(name) {
+            Ok(model) => MarkovGenerator::new(
+                model,
+                config.separator.clone().unwrap_or_else(|| "-".to_string()),
+                config.length.or(config.min_length).unwrap_or(16),
+                config.min_entropy.unwrap_or(0.0),
+            )
+            .map(Generator::Markov),
+            Err(e) => Err(format!("Could not load Markov model: {}", e)),
+        }
     } else {
-        let min = config.min_length.unwrap_or(16); // Default minimum length
-        let max = config.max_length.unwrap_or(min); // Default max equals min
-        (min, max)
-    };
+        let mut constraints = config.password_constraints(symbols);
 
-    // Build password constraints
-    let constraints = PasswordConstraints {
-        min_numeric: config.min_numeric,
-        max_numeric: config.max_numeric,
-        min_lower: config.min_lower,
-        max_lower: config.max_lower,
-        min_upper: config.min_upper,
-        max_upper: config.max_upper,
-        min_symbol: config.min_symbol,
-        max_symbol: config.max_symbol,
-        min_length,
-        max_length,
-        symbols: config.symbols.unwrap_or_else(|| "!@#$%^&*()_+-=[]{}|;:,.<>?".to_string()),
-        exclude_ambiguous: config.exclude_ambiguous.unwrap_or(false),
+        let pronounceable = config.pronounceable.unwrap_or(false);
+
+        // An entropy target fixes the length at the shortest that reaches it
+        let target_length = match config.min_entropy {
+            Some(bits) if pronounceable => {
+                PronounceableGenerator::length_for_entropy(&constraints, bits).map(Some)
+            }
+            Some(bits) => constraints.length_for_entropy(bits).map(Some),
+            None => Ok(None),
+        };
+
+        target_length.and_then(|target_length| {
+            if let Some(length) = target_length {
+                eprintln!(
+                    "Entropy target: {} bits, using {} characters",
+                    config.min_entropy.unwrap_or_default(),
+                    length
+                );
+                constraints.min_length = length;
+                constraints.max_length = length;
+            }
+            let average_length =
+                (constraints.min_length as f64 + constraints.max_length as f64) / 2.0;
+
+            if pronounceable {
+                PronounceableGenerator::new(constraints).map(|generator| {
+                    // Pronounceable output has less entropy per character, so say how much
+                    let entropy = generator.entropy();
+                    eprintln!(
+                        "Pronounceable: ~{:.1} bits of entropy per password ({:.2} per character), at least {:.1} bits",
+                        entropy.average,
+                        entropy.average / average_length,
+                        entropy.minimum
+                    );
+                    Generator::Pronounceable(generator)
+                })
+            } else if config.uniform.unwrap_or(false) || target_length.is_some() {
+                // The target is measured on the uniform keyspace, so sample uniformly
+                PasswordGenerator::uniform(constraints).map(|generator| {
+                    let keyspace = generator.keyspace();
+                    eprintln!(
+                        "Uniform: keyspace {} ({:.1} bits of entropy)",
+                        keyspace,
+                        keyspace.log2()
+                    );
+                    Generator::Password(generator)
+                })
+            } else {
+                PasswordGenerator::new(constraints).map(Generator::Password)
+            }
+        })
     };
 
-    // Create password generator
-    let generator = match PasswordGenerator::new(constraints) {
+    // Bail out if the options are contradictory
+    let generator = match generator {
         Ok(generator) => generator,
         Err(e) => {
             eprintln!("Error: {}", e);
@@ -309,13 +743,39 @@ fn main() {
         }
     };
 
+    // Report the keyspace instead of generating
+    if cli.entropy {
+        let uniform = config.uniform.unwrap_or(false) || config.min_entropy.is_some();
+        if let Err(e) = print_entropy(&generator, uniform) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // A fixed seed replays the same passwords, which only suits tests.
+    // Otherwise a DRBG of our own serves the whole batch without locking.
+    let mut rng: Box<dyn RandomSource> = match cli.insecure_seed {
+        Some(seed) => {
+            eprintln!("Warning: --insecure-seed makes every password predictable; use it only for tests");
+            Box::new(SeededRandom::new(seed))
+        }
+        None => match ChaChaRng::new() {
+            Ok(rng) => Box::new(rng),
+            Err(e) => {
+                eprintln!("Error seeding random generator: {}", e);
+                process::exit(1);
+            }
+        },
+    };
+
     // Generate passwords
     let count = config.count.unwrap_or(1);
     let copy_enabled = config.copy.unwrap_or(false);
     let mut last_password: Option<String> = None;
 
     for i in 0..count {
-        match generator.generate() {
+        match generator.generate(rng.as_mut()) {
             Ok(password) => {
                 println!("{}", password);
 
@@ -332,10 +792,10 @@ fn main() {
     }
 
     // Copy last password to clipboard if requested
-    if let Some(password) = last_password {
-        if let Err(e) = clipboard::copy_to_clipboard(&password) {
-            eprintln!("Warning: Failed to copy to clipboard: {}", e);
-            // Don't exit - password was still printed successfully
-        }
+    if let Some(password) = last_password
+        && let Err(e) = clipboard::copy_to_clipboard(&password)
+    {
+        eprintln!("Warning: Failed to copy to clipboard: {}", e);
+        // Don't exit - password was still printed successfully
     }
 }
@@ -0,0 +1,500 @@
+use crate::config::Config;
+use crate::random::{RandomSource, SecureRandom};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Padding before the first letter of a word
+const START: char = '^';
+/// Marks the end of a word
+const END: char = '$';
+
+/// Smallest and largest supported n-gram sizes
+pub const MIN_NGRAM: usize = 2;
+pub const MAX_NGRAM: usize = 6;
+
+/// Gives up if this many words still don't reach the requested length and entropy
+const MAX_WORDS: usize = 1000;
+
+/// A character n-gram model of the words in a text
+///
+/// Each context is the previous `ngram - 1` characters of a word (padded
+/// with `^` at the start) and maps to counts of the next character, with
+/// `$` marking the end of the word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkovModel {
+    ngram: usize,
+    transitions: BTreeMap<String, Vec<(char, u32)>>,
+}
+
+impl MarkovModel {
+    /// Learn a model from the words in a text
+    ///
+    /// Words are runs of alphabetic characters, lowercased.
+    pub fn train(text: &str, ngram: usize) -> Result<Self, String> {
+        Self::check_ngram(ngram)?;
+
+        let mut counts: BTreeMap<String, BTreeMap<char, u32>> = BTreeMap::new();
+        for word in text.split(|c: char| !c.is_alphabetic()) {
+            if word.is_empty() {
+                continue;
+            }
+
+            let mut context: Vec<char> = vec![START; ngram - 1];
+            for c in word.chars().flat_map(char::to_lowercase).chain([END]) {
+                let count = counts
+                    .entry(context.iter().collect())
+                    .or_default()
+                    .entry(c)
+                    .or_default();
+                *count = count.saturating_add(1);
+
+                context.remove(0);
+                context.push(c);
+            }
+        }
+
+        if counts.is_empty() {
+            return Err("corpus contains no words".to_string());
+        }
+
+        let transitions = counts
+            .into_iter()
+            .map(|(context, next)| (context, next.into_iter().collect()))
+            .collect();
+
+        let model = Self { ngram, transitions };
+        model.check_totals()?;
+        Ok(model)
+    }
+
+    /// Parse a model saved by `serialize`
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut ngram = None;
+        let mut transitions: BTreeMap<String, Vec<(char, u32)>> = BTreeMap::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line_number = index + 1;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(value) = line.strip_prefix("ngram=") {
+                let value = value.parse().map_err(|_| {
+                    format!("line {}: invalid n-gram size '{}'", line_number, value)
+                })?;
+                Self::check_ngram(value)?;
+                ngram = Some(value);
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [context, next, count] = fields.as_slice() else {
+                return Err(format!(
+                    "line {}: expected context, character and count",
+                    line_number
+                ));
+            };
+            let mut next_chars = next.chars();
+            let (Some(next), None) = (next_chars.next(), next_chars.next()) else {
+                return Err(format!("line {}: expected a single character", line_number));
+            };
+            let count = match count.parse() {
+                Ok(count) if count > 0 => count,
+                _ => return Err(format!("line {}: invalid count '{}'", line_number, count)),
+            };
+
+            transitions
+                .entry(context.to_string())
+                .or_default()
+                .push((next, count));
+        }
+
+        let ngram = ngram.ok_or("missing n-gram size")?;
+        if transitions
+            .keys()
+            .any(|context| context.chars().count() != ngram - 1)
+        {
+            return Err(format!(
+                "context length does not match n-gram size {}",
+                ngram
+            ));
+        }
+        if !transitions.contains_key(&START.to_string().repeat(ngram - 1)) {
+            return Err("model has no word starts".to_string());
+        }
+
+        let model = Self { ngram, transitions };
+        model.check_totals()?;
+        model.check_words_end()?;
+        Ok(model)
+    }
+
+    /// Serialize the model as `context<TAB>char<TAB>count` lines
+    pub fn serialize(&self) -> String {
+        let mut content = format!("# genpass markov model\nngram={}\n", self.ngram);
+        for (context, next) in &self.transitions {
+            for (c, count) in next {
+                content.push_str(&format!("{}\t{}\t{}\n", context, c, count));
+            }
+        }
+        content
+    }
+
+    /// Get the path to a named model (~/.genpass/models/<name>)
+    pub fn model_path(name: &str) -> io::Result<PathBuf> {
+        Ok(Config::config_dir()?.join("models").join(name))
+    }
+
+    /// Load a named model
+    pub fn load(name: &str) -> io::Result<Self> {
+        let path = Self::model_path(name)?;
+        let content = fs::read_to_string(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        Self::parse(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Save the model under a name
+    pub fn save(&self, name: &str) -> io::Result<()> {
+        let path = Self::model_path(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.serialize())
+    }
+
+    /// Number of distinct contexts in the model
+    pub fn contexts(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Sample one word, returning it with its exact entropy in bits
+    ///
+    /// The entropy is the word's self-information, -log2 P(word), from
+    /// the model's transition probabilities.
+    pub fn sample_word(&self, rng: &mut dyn RandomSource) -> io::Result<(String, f64)> {
+        let mut context: Vec<char> = vec![START; self.ngram - 1];
+        let mut word = String::new();
+        let mut bits = 0.0;
+
+        loop {
+            let key: String = context.iter().collect();
+            let next = self.transitions.get(&key).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "model has a dead end")
+            })?;
+
+            let total: u32 = next.iter().map(|(_, count)| count).sum();
+            let mut pick = rng.random_range(total as usize)? as u32;
+            let (c, count) = next
+                .iter()
+                .find(|(_, count)| {
+                    if pick < *count {
+                        true
+                    } else {
+                        pick -= count;
+                        false
+                    }
+                })
+                .copied()
+                .expect("pick is below the total");
+
+            bits += (total as f64 / count as f64).log2();
+            if c == END {
+                return Ok((word, bits));
+            }
+
+            word.push(c);
+            context.remove(0);
+            context.push(c);
+        }
+    }
+
+    /// Exact entropy of a word under the model, or None if it cannot be generated
+    pub fn word_entropy(&self, word: &str) -> Option<f64> {
+        let mut context: Vec<char> = vec![START; self.ngram - 1];
+        let mut bits = 0.0;
+
+        for c in word.chars().chain([END]) {
+            let next = self.transitions.get(&context.iter().collect::<String>())?;
+            let total: u32 = next.iter().map(|(_, count)| count).sum();
+            let (_, count) = next.iter().find(|(n, _)| *n == c)?;
+            bits += (total as f64 / *count as f64).log2();
+
+            context.remove(0);
+            context.push(c);
+        }
+
+        Some(bits)
+    }
+
+    fn check_ngram(ngram: usize) -> Result<(), String> {
+        if (MIN_NGRAM..=MAX_NGRAM).contains(&ngram) {
+            Ok(())
+        } else {
+            Err(format!(
+                "n-gram size must be between {} and {}, got {}",
+                MIN_NGRAM, MAX_NGRAM, ngram
+            ))
+        }
+    }
+
+    /// Every context a word can reach must have transitions and a way on
+    /// to END, or `sample_word` could fail or walk forever
+    ///
+    /// Trained models always pass; this guards hand-edited or truncated
+    /// model files.
+    fn check_words_end(&self) -> Result<(), String> {
+        let successor =
+            |context: &str, c: char| -> String { context.chars().skip(1).chain([c]).collect() };
+
+        // Contexts that can end a word, grown backwards from the ones with
+        // an END transition
+        let mut ending: BTreeSet<&str> = BTreeSet::new();
+        loop {
+            let before = ending.len();
+            for (context, next) in &self.transitions {
+                if !ending.contains(context.as_str())
+                    && next
+                        .iter()
+                        .any(|&(c, _)| c == END || ending.contains(successor(context, c).as_str()))
+                {
+                    ending.insert(context);
+                }
+            }
+            if ending.len() == before {
+                break;
+            }
+        }
+
+        let start = START.to_string().repeat(self.ngram - 1);
+        let mut seen = BTreeSet::from([start.clone()]);
+        let mut pending = vec![start];
+        while let Some(context) = pending.pop() {
+            let Some(next) = self.transitions.get(&context) else {
+                return Err(format!("model has a dead end after '{}'", context));
+            };
+            if !ending.contains(context.as_str()) {
+                return Err(format!("words reaching '{}' can never end", context));
+            }
+            for &(c, _) in next {
+                if c != END {
+                    let context = successor(&context, c);
+                    if seen.insert(context.clone()) {
+                        pending.push(context);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts are sampled with `random_range`, which works on u32
+    fn check_totals(&self) -> Result<(), String> {
+        for next in self.transitions.values() {
+            next.iter()
+                .try_fold(0u32, |total, (_, count)| total.checked_add(*count))
+                .ok_or("transition counts are too large")?;
+        }
+        Ok(())
+    }
+}
+
+/// Generator for word-like passwords sampled from a Markov model
+///
+/// Words are joined with the separator until the password is at least
+/// `min_length` characters long and carries at least `min_entropy` bits.
+/// The stopping rule depends only on the words drawn so far and the
+/// separator cannot appear inside a word, so the entropy of a password
+/// is exactly the sum of its words' entropies.
+pub struct MarkovGenerator {
+    model: MarkovModel,
+    separator: String,
+    min_length: usize,
+    min_entropy: f64,
+}
+
+impl MarkovGenerator {
+    /// Create a new generator from a trained model
+    pub fn new(
+        model: MarkovModel,
+        separator: String,
+        min_length: usize,
+        min_entropy: f64,
+    ) -> Result<Self, String> {
+        if separator.is_empty() || separator.chars().any(char::is_alphabetic) {
+            return Err(
+                "Markov passwords need a separator without letters, so words can be told apart"
+                    .to_string(),
+            );
+        }
+        if !min_entropy.is_finite() || min_entropy < 0.0 {
+            return Err("Minimum entropy must be a non-negative number of bits".to_string());
+        }
+
+        Ok(Self {
+            model,
+            separator,
+            min_length,
+            min_entropy,
+        })
+    }
+
+    /// Generate a password
+    pub fn generate(&self) -> io::Result<String> {
+        self.generate_with(&mut SecureRandom)
+    }
+
+    /// Entropy every password reaches, in bits
+    ///
+    /// Words are added until a password carries this much, so it is a lower
+    /// bound for each password and for the generator as a whole.
+    pub fn min_entropy(&self) -> f64 {
+        self.min_entropy
+    }
+
+    /// Generate a password using the given random source
+    pub fn generate_with(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        self.generate_with_entropy(rng)
+            .map(|(password, _)| password)
+    }
+
+    /// Generate a password, returning it with its exact entropy in bits
+    pub fn generate_with_entropy(&self, rng: &mut dyn RandomSource) -> io::Result<(String, f64)> {
+        let mut words = Vec::new();
+        let mut length = 0;
+        let mut bits = 0.0;
+
+        while words.is_empty() || length < self.min_length || bits < self.min_entropy {
+            if words.len() == MAX_WORDS {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Markov model cannot reach the requested length and entropy",
+                ));
+            }
+
+            let (word, word_bits) = self.model.sample_word(rng)?;
+            if !words.is_empty() {
+                length += self.separator.chars().count();
+            }
+            length += word.chars().count();
+            bits += word_bits;
+            words.push(word);
+        }
+
+        Ok((words.join(&self.separator), bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &str = "the cat sat on the mat. The rat ate the hat!";
+
+    #[test]
+    fn test_train_counts() {
+        let model = MarkovModel::train(CORPUS, 2).unwrap();
+
+        // Eleven words, four of them "the" (case is folded)
+        let starts = &model.transitions["^"];
+        let total: u32 = starts.iter().map(|(_, c)| c).sum();
+        assert_eq!(total, 11);
+        assert!(starts.contains(&('t', 4)));
+
+        // "h" is followed by "e" four times and by "a" once
+        assert_eq!(model.transitions["h"], vec![('a', 1), ('e', 4)]);
+    }
+
+    #[test]
+    fn test_word_entropy() {
+        let model = MarkovModel::train("ab ab ac", 3).unwrap();
+        // "a" is certain, then b (2/3), then the end (certain)
+        let bits = model.word_entropy("ab").unwrap();
+        assert!((bits - (3f64 / 2.0).log2()).abs() < 1e-9);
+        assert!((model.word_entropy("ac").unwrap() - 3f64.log2()).abs() < 1e-9);
+        assert_eq!(model.word_entropy("ba"), None);
+    }
+
+    #[test]
+    fn test_sampled_entropy_matches_model() {
+        let model = MarkovModel::train(CORPUS, 3).unwrap();
+        for _ in 0..20 {
+            let (word, bits) = model.sample_word(&mut SecureRandom).unwrap();
+            let expected = model.word_entropy(&word).unwrap();
+            assert!((bits - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let model = MarkovModel::train(CORPUS, 4).unwrap();
+        let parsed = MarkovModel::parse(&model.serialize()).unwrap();
+        assert_eq!(parsed, model);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_models() {
+        assert!(MarkovModel::parse("ngram=3\n^^\ta\n").is_err());
+        assert!(MarkovModel::parse("ngram=3\n^^\ta\t0\n").is_err());
+        assert!(MarkovModel::parse("ngram=3\n^\ta\t1\n").is_err());
+        assert!(MarkovModel::parse("^^\ta\t1\n").is_err());
+        assert!(MarkovModel::parse("ngram=9\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_models_that_never_end() {
+        // After 'a' only 'a' follows, so a word never ends
+        let looping = "ngram=2\n^\ta\t1\na\ta\t1\n";
+        assert!(MarkovModel::parse(looping).is_err());
+        // 'b' has no transitions at all
+        let dead_end = "ngram=2\n^\ta\t1\na\t$\t1\na\tb\t1\n";
+        assert!(MarkovModel::parse(dead_end).is_err());
+        // A loop is fine while it can still be left
+        let leaves = "ngram=2\n^\ta\t1\na\ta\t1\na\t$\t1\n";
+        assert!(MarkovModel::parse(leaves).is_ok());
+    }
+
+    #[test]
+    fn test_train_rejects_empty_corpus() {
+        assert!(MarkovModel::train("123 456 !!", 3).is_err());
+        assert!(MarkovModel::train(CORPUS, 1).is_err());
+    }
+
+    #[test]
+    fn test_generator_reaches_minimums() {
+        let model = MarkovModel::train(CORPUS, 2).unwrap();
+        let generator = MarkovGenerator::new(model.clone(), "-".to_string(), 12, 20.0).unwrap();
+
+        for _ in 0..10 {
+            let (password, bits) = generator.generate_with_entropy(&mut SecureRandom).unwrap();
+            assert!(password.chars().count() >= 12);
+            assert!(bits >= 20.0);
+
+            let sum: f64 = password
+                .split('-')
+                .map(|word| model.word_entropy(word).unwrap())
+                .sum();
+            assert!((bits - sum).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_generator_rejects_letter_separator() {
+        let model = MarkovModel::train(CORPUS, 3).unwrap();
+        assert!(MarkovGenerator::new(model.clone(), "".to_string(), 8, 0.0).is_err());
+        assert!(MarkovGenerator::new(model, "x".to_string(), 8, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_deterministic_model_gives_up() {
+        let model = MarkovModel::train("abc abc", 3).unwrap();
+        let generator = MarkovGenerator::new(model, "-".to_string(), 1, 10.0).unwrap();
+        assert!(generator.generate().is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use crate::bigint::BigUint;
+use crate::generator::{self, LOWERCASE, NUMERIC, UPPERCASE};
+use crate::random::{RandomSource, SecureRandom};
+use std::io;
+
+/// Character sets available to a mask
+#[derive(Debug, Clone, Default)]
+pub struct MaskOptions {
+    pub symbols: String,
+    pub exclude_ambiguous: bool,
+    /// User-defined charsets for `?1` to `?4`
+    pub charsets: [Option<String>; 4],
+}
+
+/// A hashcat-style mask: one charset per position
+///
+/// Tokens are `?l` (lowercase), `?u` (uppercase), `?d` (digits),
+/// `?s` (the configured symbols), `?a` (all four), `?1`-`?4` (custom
+/// charsets) and `??` for a literal `?`. Any other character is a literal.
+#[derive(Debug, Clone)]
+pub struct Mask {
+    positions: Vec<Vec<char>>,
+}
+
+impl Mask {
+    /// Parse a mask, resolving each token to its filtered charset
+    pub fn parse(mask: &str, options: &MaskOptions) -> Result<Self, String> {
+        // Custom charsets may use the built-in tokens but not each other
+        let mut custom: [Option<Vec<char>>; 4] = Default::default();
+        for (i, definition) in options.charsets.iter().enumerate() {
+            if let Some(definition) = definition {
+                let mut chars = Vec::new();
+                for token in Self::tokens(definition)? {
+                    let set = match token {
+                        Token::Literal(c) => vec![c],
+                        Token::Builtin(set) => Self::builtin(set, options),
+                        Token::Custom(_) => {
+                            return Err(format!(
+                                "charset {} cannot refer to another custom charset",
+                                i + 1
+                            ));
+                        }
+                    };
+                    for c in set {
+                        if !chars.contains(&c) {
+                            chars.push(c);
+                        }
+                    }
+                }
+                custom[i] = Some(generator::filter_ambiguous(
+                    &chars.into_iter().collect::<String>(),
+                    options.exclude_ambiguous,
+                ));
+            }
+        }
+
+        let mut positions = Vec::new();
+        for token in Self::tokens(mask)? {
+            let set = match token {
+                Token::Literal(c) => vec![c],
+                Token::Builtin(set) => Self::builtin(set, options),
+                Token::Custom(n) => custom[n - 1]
+                    .clone()
+                    .ok_or_else(|| format!("mask uses ?{} but --charset{} is not set", n, n))?,
+            };
+            if set.is_empty() {
+                return Err(format!(
+                    "mask position {} has no characters left after filtering",
+                    positions.len() + 1
+                ));
+            }
+            positions.push(set);
+        }
+
+        if positions.is_empty() {
+            return Err("mask is empty".to_string());
+        }
+
+        Ok(Self { positions })
+    }
+
+    /// Number of characters the mask produces
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Whether the mask has no positions
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Number of distinct passwords the mask can produce
+    pub fn keyspace(&self) -> BigUint {
+        self.positions.iter().fold(BigUint::one(), |total, set| {
+            total.mul_small(set.len() as u32)
+        })
+    }
+
+    /// Entropy of a generated password in bits
+    ///
+    /// Every position is drawn uniformly and independently, so this is
+    /// exactly log2 of the keyspace.
+    pub fn entropy(&self) -> f64 {
+        self.positions
+            .iter()
+            .map(|set| (set.len() as f64).log2())
+            .sum()
+    }
+
+    /// Generate a password matching the mask
+    pub fn generate(&self) -> io::Result<String> {
+        self.generate_with(&mut SecureRandom)
+    }
+
+    /// Generate a password matching the mask using the given random source
+    pub fn generate_with(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        let mut password = String::with_capacity(self.positions.len());
+        for set in &self.positions {
+            password.push(set[rng.random_range(set.len())?]);
+        }
+        Ok(password)
+    }
+
+    /// The filtered characters of a built-in charset
+    fn builtin(set: char, options: &MaskOptions) -> Vec<char> {
+        let chars = match set {
+            'l' => LOWERCASE.to_string(),
+            'u' => UPPERCASE.to_string(),
+            'd' => NUMERIC.to_string(),
+            's' => options.symbols.clone(),
+            _ => format!("{}{}{}{}", LOWERCASE, UPPERCASE, NUMERIC, options.symbols),
+        };
+        generator::unique_chars(generator::filter_ambiguous(
+            &chars,
+            options.exclude_ambiguous,
+        ))
+    }
+
+    /// Split a mask or charset definition into tokens
+    fn tokens(mask: &str) -> Result<Vec<Token>, String> {
+        let mut tokens = Vec::new();
+        let mut chars = mask.chars();
+        while let Some(c) = chars.next() {
+            if c != '?' {
+                tokens.push(Token::Literal(c));
+                continue;
+            }
+
+            tokens.push(match chars.next() {
+                Some('?') => Token::Literal('?'),
+                Some(set @ ('l' | 'u' | 'd' | 's' | 'a')) => Token::Builtin(set),
+                Some(n @ '1'..='4') => Token::Custom(n as usize - '0' as usize),
+                Some(other) => return Err(format!("unknown mask token '?{}'", other)),
+                None => return Err("mask ends with a lone '?'; use '??' for a literal".to_string()),
+            });
+        }
+        Ok(tokens)
+    }
+}
+
+/// One parsed mask token
+#[derive(Debug, Clone, Copy)]
+enum Token {
+    Literal(char),
+    Builtin(char),
+    Custom(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> MaskOptions {
+        MaskOptions {
+            symbols: "!@#$".to_string(),
+            exclude_ambiguous: false,
+            charsets: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_shape() {
+        let mask = Mask::parse("?u?d?d?d?d?d?d?s", &options()).unwrap();
+        assert_eq!(mask.len(), 8);
+
+        let password: Vec<char> = mask.generate().unwrap().chars().collect();
+        assert!(password[0].is_ascii_uppercase());
+        assert!(password[1..7].iter().all(|c| c.is_ascii_digit()));
+        assert!("!@#$".contains(password[7]));
+    }
+
+    #[test]
+    fn test_keyspace_and_entropy() {
+        let mask = Mask::parse("?l?d-??", &options()).unwrap();
+        assert_eq!(mask.keyspace().to_string(), "260");
+        assert!((mask.entropy() - 260f64.log2()).abs() < 1e-9);
+
+        // ?a is 26 + 26 + 10 + 4 characters
+        let mask = Mask::parse(&"?a".repeat(20), &options()).unwrap();
+        assert_eq!(
+            mask.keyspace().to_string(),
+            "2459680572025003612127586705579442176"
+        );
+        assert!((mask.entropy() - 20.0 * 66f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_literals() {
+        let mask = Mask::parse("id-??-?d", &options()).unwrap();
+        let password = mask.generate().unwrap();
+        assert!(password.starts_with("id-?-"));
+        assert_eq!(mask.keyspace().to_string(), "10");
+    }
+
+    #[test]
+    fn test_custom_charsets() {
+        let mut opts = options();
+        opts.charsets[0] = Some("?dabc".to_string());
+        opts.charsets[3] = Some("xyzx".to_string());
+
+        let mask = Mask::parse("?1?4", &opts).unwrap();
+        // Duplicates in a charset are counted once
+        assert_eq!(mask.keyspace().to_string(), "39");
+
+        assert!(Mask::parse("?2", &opts).is_err());
+
+        opts.charsets[1] = Some("?1".to_string());
+        assert!(Mask::parse("?l", &opts).is_err());
+    }
+
+    #[test]
+    fn test_exclude_ambiguous() {
+        let mut opts = options();
+        opts.exclude_ambiguous = true;
+        opts.charsets[0] = Some("01l".to_string());
+
+        let mask = Mask::parse("?d?l?u", &opts).unwrap();
+        assert_eq!(mask.keyspace().to_string(), (8 * 25 * 24).to_string());
+
+        // A custom charset made only of ambiguous characters is empty
+        assert!(Mask::parse("?1", &opts).is_err());
+    }
+
+    #[test]
+    fn test_invalid_masks() {
+        assert!(Mask::parse("", &options()).is_err());
+        assert!(Mask::parse("?d?", &options()).is_err());
+        assert!(Mask::parse("?x", &options()).is_err());
+
+        let mut opts = options();
+        opts.symbols = String::new();
+        assert!(Mask::parse("?s", &opts).is_err());
+
+        // Repeated symbols don't inflate the keyspace
+        opts.symbols = "!!a".to_string();
+        assert_eq!(
+            Mask::parse("?s", &opts).unwrap().keyspace().to_string(),
+            "2"
+        );
+        assert_eq!(
+            Mask::parse("?a", &opts).unwrap().keyspace().to_string(),
+            "63"
+        );
+    }
+}
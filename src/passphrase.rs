@@ -0,0 +1,524 @@
+use crate::generator::{self, ROMAJI_SYLLABLES};
+use crate::random::{RandomSource, SecureRandom};
+use crate::wordlist::Wordlist;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+/// Digits used when inserting a number into a passphrase
+const DIGITS: &str = "0123456789";
+
+/// How each word of a passphrase is capitalized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Capitalization {
+    /// all lowercase (default)
+    #[default]
+    Lower,
+    /// First letter of each word uppercase
+    First,
+    /// ALL UPPERCASE
+    Upper,
+    /// Each word randomly lowercase or capitalized
+    Random,
+}
+
+impl Capitalization {
+    /// Name used on the command line and in config files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capitalization::Lower => "lower",
+            Capitalization::First => "first",
+            Capitalization::Upper => "upper",
+            Capitalization::Random => "random",
+        }
+    }
+}
+
+impl FromStr for Capitalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lower" => Ok(Capitalization::Lower),
+            "first" => Ok(Capitalization::First),
+            "upper" => Ok(Capitalization::Upper),
+            "random" => Ok(Capitalization::Random),
+            _ => Err(format!(
+                "invalid capitalization '{}' (expected lower, first, upper or random)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Capitalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where passphrase words are chosen from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiceMode {
+    /// Words are chosen by the system RNG (default)
+    #[default]
+    Off,
+    /// Words are chosen only by physical dice rolls typed by the user
+    Only,
+    /// Dice rolls are combined with the system RNG
+    Mixed,
+}
+
+impl DiceMode {
+    /// Name used in config files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiceMode::Off => "off",
+            DiceMode::Only => "only",
+            DiceMode::Mixed => "mixed",
+        }
+    }
+}
+
+impl FromStr for DiceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(DiceMode::Off),
+            "only" => Ok(DiceMode::Only),
+            "mixed" => Ok(DiceMode::Mixed),
+            _ => Err(format!(
+                "invalid dice mode '{}' (expected off, only or mixed)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DiceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Options for passphrase generation
+#[derive(Debug, Clone)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    pub capitalization: Capitalization,
+    pub add_digit: bool,
+    pub add_symbol: bool,
+    pub symbols: String,
+    pub dice: DiceMode,
+}
+
+/// Where passphrase words come from
+enum WordSource {
+    List(Wordlist),
+    /// Random words of this many romaji syllables
+    Romaji(usize),
+}
+
+/// Diceware-style passphrase generator
+pub struct PassphraseGenerator {
+    options: PassphraseOptions,
+    source: WordSource,
+    symbol_chars: Vec<char>,
+}
+
+impl PassphraseGenerator {
+    /// Create a new passphrase generator drawing from the given wordlist
+    pub fn new(options: PassphraseOptions, wordlist: Wordlist) -> Result<Self, String> {
+        if wordlist.is_empty() {
+            return Err("Wordlist is empty".to_string());
+        }
+        if options.dice != DiceMode::Off {
+            wordlist.check_dice()?;
+        }
+
+        Self::with_source(options, WordSource::List(wordlist))
+    }
+
+    /// Create a passphrase generator whose words are random romaji syllables
+    pub fn romaji(options: PassphraseOptions, syllables: usize) -> Result<Self, String> {
+        if syllables == 0 {
+            return Err("Romaji words must have at least one syllable".to_string());
+        }
+        if options.dice != DiceMode::Off {
+            return Err("Dice input needs a wordlist and cannot be used with romaji".to_string());
+        }
+
+        Self::with_source(options, WordSource::Romaji(syllables))
+    }
+
+    fn with_source(options: PassphraseOptions, source: WordSource) -> Result<Self, String> {
+        if options.words == 0 {
+            return Err("Passphrase must contain at least one word".to_string());
+        }
+
+        let symbol_chars: Vec<char> = options.symbols.chars().collect();
+        if options.add_symbol && symbol_chars.is_empty() {
+            return Err("Cannot insert a symbol: symbol set is empty".to_string());
+        }
+
+        if options.dice != DiceMode::Off {
+            // Everything random must come from the dice in dice mode
+            if options.capitalization == Capitalization::Random
+                || options.add_digit
+                || options.add_symbol
+            {
+                return Err(
+                    "Dice input cannot be combined with random capitalization or inserted digits/symbols"
+                        .to_string(),
+                );
+            }
+        }
+
+        Ok(Self {
+            options,
+            source,
+            symbol_chars,
+        })
+    }
+
+    /// Entropy contributed by one word, in bits
+    pub fn entropy_per_word(&self) -> f64 {
+        match self.source {
+            WordSource::List(ref wordlist) => wordlist.entropy_per_word(),
+            WordSource::Romaji(syllables) => {
+                syllables as f64 * (ROMAJI_SYLLABLES.len() as f64).log2()
+            }
+        }
+    }
+
+    /// Number of words in each passphrase
+    pub fn words(&self) -> usize {
+        self.options.words
+    }
+
+    /// Entropy of the words in a passphrase, in bits
+    ///
+    /// Capitalization and inserted digits or symbols are not counted.
+    pub fn entropy(&self) -> f64 {
+        self.options.words as f64 * self.entropy_per_word()
+    }
+
+    /// Add words until the passphrase carries at least `bits` of entropy
+    pub fn ensure_entropy(&mut self, bits: f64) {
+        let per_word = self.entropy_per_word();
+        if per_word > 0.0 {
+            let needed = (bits / per_word).ceil() as usize;
+            self.options.words = self.options.words.max(needed);
+        }
+    }
+
+    /// Generate a passphrase
+    ///
+    /// In dice mode the rolls are read from stdin.
+    pub fn generate(&self) -> io::Result<String> {
+        self.generate_with(&mut SecureRandom)
+    }
+
+    /// Generate a passphrase using the given random source
+    ///
+    /// In dice mode the rolls are read from stdin.
+    pub fn generate_with(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        if self.options.dice == DiceMode::Off {
+            self.generate_random(rng)
+        } else {
+            let stdin = io::stdin();
+            self.generate_from_dice(&mut stdin.lock(), rng)
+        }
+    }
+
+    /// Generate a passphrase using only the random source
+    fn generate_random(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        let mut words = Vec::with_capacity(self.options.words);
+        for _ in 0..self.options.words {
+            let word = match self.source {
+                WordSource::List(ref wordlist) => {
+                    let index = rng.random_range(wordlist.len())?;
+                    wordlist.get(index).unwrap_or_default().to_string()
+                }
+                WordSource::Romaji(syllables) => generator::romaji_word(syllables, rng)?,
+            };
+            words.push(self.capitalize(&word, rng)?);
+        }
+
+        // Append the extra digit/symbol to randomly chosen words
+        if self.options.add_digit {
+            let digit = DIGITS.as_bytes()[rng.random_range(DIGITS.len())?] as char;
+            let target = rng.random_range(words.len())?;
+            words[target].push(digit);
+        }
+        if self.options.add_symbol {
+            let symbol = self.symbol_chars[rng.random_range(self.symbol_chars.len())?];
+            let target = rng.random_range(words.len())?;
+            words[target].push(symbol);
+        }
+
+        Ok(words.join(&self.options.separator))
+    }
+
+    /// Generate a passphrase from d6 rolls, one word per line of input
+    ///
+    /// Invalid lines are reported and asked for again. Prompts go to stderr
+    /// so that stdout only carries the passphrase.
+    pub fn generate_from_dice<R: BufRead>(
+        &self,
+        reader: &mut R,
+        rng: &mut dyn RandomSource,
+    ) -> io::Result<String> {
+        let WordSource::List(ref wordlist) = self.source else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "dice input needs a wordlist",
+            ));
+        };
+        let dice = wordlist
+            .check_dice()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let mut words = Vec::with_capacity(self.options.words);
+        while words.len() < self.options.words {
+            eprint!(
+                "  Word {} of {} - roll {} dice: ",
+                words.len() + 1,
+                self.options.words,
+                dice
+            );
+
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "dice input ended before all words were rolled",
+                ));
+            }
+
+            let mut index = match wordlist.index_from_rolls(&line) {
+                Ok(index) => index,
+                Err(e) => {
+                    eprintln!("  Invalid rolls: {}, please try again.", e);
+                    continue;
+                }
+            };
+
+            if self.options.dice == DiceMode::Mixed {
+                // Adding an independent offset mod n is uniform if either source is
+                let offset = rng.random_range(wordlist.len())?;
+                index = (index + offset) % wordlist.len();
+            }
+
+            let word = wordlist.get(index).unwrap_or_default();
+            words.push(self.capitalize(word, rng)?);
+        }
+
+        Ok(words.join(&self.options.separator))
+    }
+
+    /// Apply the configured capitalization to a word
+    fn capitalize(&self, word: &str, rng: &mut dyn RandomSource) -> io::Result<String> {
+        let capitalization = match self.options.capitalization {
+            Capitalization::Random => {
+                if rng.random_range(2)? == 0 {
+                    Capitalization::Lower
+                } else {
+                    Capitalization::First
+                }
+            }
+            other => other,
+        };
+
+        Ok(capitalize(word, capitalization))
+    }
+}
+
+/// Capitalize a word; `Random` is treated like `First`
+pub(crate) fn capitalize(word: &str, capitalization: Capitalization) -> String {
+    match capitalization {
+        Capitalization::Lower => word.to_lowercase(),
+        Capitalization::Upper => word.to_uppercase(),
+        Capitalization::First | Capitalization::Random => {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::SeededRandom;
+
+    fn options(words: usize) -> PassphraseOptions {
+        PassphraseOptions {
+            words,
+            separator: "-".to_string(),
+            capitalization: Capitalization::Lower,
+            add_digit: false,
+            add_symbol: false,
+            symbols: "!@#$".to_string(),
+            dice: DiceMode::Off,
+        }
+    }
+
+    #[test]
+    fn test_basic_passphrase() {
+        let generator = PassphraseGenerator::new(options(6), Wordlist::eff_short()).unwrap();
+        let passphrase = generator.generate().unwrap();
+
+        let words: Vec<&str> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 6);
+        for word in words {
+            assert!(!word.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_golden_passphrase() {
+        let mut opts = options(4);
+        opts.add_digit = true;
+        let generator = PassphraseGenerator::new(opts, Wordlist::eff_short()).unwrap();
+        let passphrase = generator.generate_with(&mut SeededRandom::new(1)).unwrap();
+        assert_eq!(passphrase, "owl3-thud-pout-blank");
+    }
+
+    #[test]
+    fn test_capitalization() {
+        let mut opts = options(4);
+        opts.capitalization = Capitalization::First;
+        opts.separator = " ".to_string();
+        let generator = PassphraseGenerator::new(opts, Wordlist::eff_short()).unwrap();
+        let passphrase = generator.generate().unwrap();
+
+        for word in passphrase.split(' ') {
+            assert!(word.chars().next().unwrap().is_uppercase());
+        }
+    }
+
+    #[test]
+    fn test_add_digit_and_symbol() {
+        let mut opts = options(3);
+        opts.add_digit = true;
+        opts.add_symbol = true;
+        let generator = PassphraseGenerator::new(opts, Wordlist::eff_short()).unwrap();
+        let passphrase = generator.generate().unwrap();
+
+        assert_eq!(passphrase.chars().filter(|c| c.is_ascii_digit()).count(), 1);
+        assert_eq!(passphrase.chars().filter(|c| "!@#$".contains(*c)).count(), 1);
+    }
+
+    #[test]
+    fn test_dice_input() {
+        let mut opts = options(3);
+        opts.dice = DiceMode::Only;
+        let generator = PassphraseGenerator::new(opts, Wordlist::eff_short()).unwrap();
+
+        // The invalid lines are skipped and asked for again
+        let mut input = io::Cursor::new("1111\n12\n1 1 1 2\n6676\n6666\n");
+        let passphrase = generator
+            .generate_from_dice(&mut input, &mut SecureRandom)
+            .unwrap();
+        assert_eq!(passphrase, "acid-acorn-zoom");
+    }
+
+    #[test]
+    fn test_dice_input_ends_early() {
+        let mut opts = options(3);
+        opts.dice = DiceMode::Only;
+        let generator = PassphraseGenerator::new(opts, Wordlist::eff_short()).unwrap();
+
+        let mut input = io::Cursor::new("1111\n");
+        assert!(
+            generator
+                .generate_from_dice(&mut input, &mut SecureRandom)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_dice_mixed_with_rng() {
+        let mut opts = options(2);
+        opts.dice = DiceMode::Mixed;
+        let list = Wordlist::eff_short();
+        let generator = PassphraseGenerator::new(opts, list.clone()).unwrap();
+
+        let mut input = io::Cursor::new("1111\n1111\n");
+        let passphrase = generator
+            .generate_from_dice(&mut input, &mut SecureRandom)
+            .unwrap();
+        for word in passphrase.split('-') {
+            assert!((0..list.len()).any(|i| list.get(i) == Some(word)));
+        }
+    }
+
+    #[test]
+    fn test_dice_rejects_rng_only_options() {
+        let mut opts = options(3);
+        opts.dice = DiceMode::Only;
+        opts.add_digit = true;
+        assert!(PassphraseGenerator::new(opts, Wordlist::eff_short()).is_err());
+
+        let mut opts = options(3);
+        opts.dice = DiceMode::Only;
+        let list = Wordlist::parse("apple\nbanana\ncherry\n").unwrap();
+        assert!(PassphraseGenerator::new(opts, list).is_err());
+    }
+
+    #[test]
+    fn test_ensure_entropy() {
+        // EFF short words carry log2(1296) = 10.34 bits each
+        let mut generator = PassphraseGenerator::new(options(2), Wordlist::eff_short()).unwrap();
+        generator.ensure_entropy(80.0);
+        assert_eq!(generator.words(), 8);
+        assert!(generator.entropy() >= 80.0);
+        assert_eq!(generator.generate().unwrap().split('-').count(), 8);
+
+        // More words than needed are kept
+        let mut generator = PassphraseGenerator::new(options(10), Wordlist::eff_short()).unwrap();
+        generator.ensure_entropy(80.0);
+        assert_eq!(generator.words(), 10);
+    }
+
+    #[test]
+    fn test_romaji_passphrase() {
+        let generator = PassphraseGenerator::romaji(options(4), 3).unwrap();
+        let passphrase = generator.generate().unwrap();
+
+        let words: Vec<&str> = passphrase.split('-').collect();
+        assert_eq!(words.len(), 4);
+        for word in words {
+            // Three syllables, each ending in its only vowel
+            let vowels = word.chars().filter(|c| "aiueo".contains(*c)).count();
+            assert_eq!(vowels, 3, "{}", word);
+            assert!(word.ends_with(['a', 'i', 'u', 'e', 'o']));
+        }
+
+        assert!((generator.entropy_per_word() - 3.0 * 68f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_romaji_rejects_dice() {
+        let mut opts = options(4);
+        opts.dice = DiceMode::Only;
+        assert!(PassphraseGenerator::romaji(opts, 3).is_err());
+        assert!(PassphraseGenerator::romaji(options(4), 0).is_err());
+    }
+
+    #[test]
+    fn test_zero_words_rejected() {
+        assert!(PassphraseGenerator::new(options(0), Wordlist::eff_short()).is_err());
+    }
+
+    #[test]
+    fn test_parse_capitalization() {
+        assert_eq!("first".parse::<Capitalization>(), Ok(Capitalization::First));
+        assert!("title".parse::<Capitalization>().is_err());
+    }
+}
@@ -0,0 +1,487 @@
+use crate::generator::PasswordConstraints;
+use crate::random::{RandomSource, SecureRandom};
+use std::io;
+
+/// Consonants that are easy to say and spell over the phone (no c, q, x, y)
+const CONSONANTS: &str = "bdfghjklmnprstvwz";
+const VOWELS: &str = "aeiou";
+const NUMERIC: &str = "0123456789";
+
+/// Letters dropped when ambiguous characters are excluded
+///
+/// `l` looks like `1`, and `i`/`o` become `I`/`O` when capitalized.
+const AMBIGUOUS_LETTERS: &[char] = &['l', 'i', 'o'];
+const AMBIGUOUS_CHARS: &[char] = &['0', '1', '|'];
+
+/// Entropy of the passwords a generator produces, in bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entropy {
+    /// Shannon entropy, averaged over the possible syllable layouts
+    pub average: f64,
+    /// Min-entropy: the bits protecting the single most likely password
+    pub minimum: f64,
+}
+
+/// How many characters of each kind go into a password of a given length
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Counts {
+    letters: usize,
+    upper: usize,
+    numeric: usize,
+    symbol: usize,
+}
+
+/// Generator for pronounceable passwords built from syllables
+///
+/// Letters alternate consonants and vowels in CV and CVC syllables.
+/// Digits and symbols are added only up to their minimums, and letters
+/// are capitalized only as far as the constraints require, so the
+/// output stays easy to read aloud. Digits, symbols and capitals are
+/// placed at random positions.
+pub struct PronounceableGenerator {
+    constraints: PasswordConstraints,
+    consonants: Vec<char>,
+    vowels: Vec<char>,
+    numeric_chars: Vec<char>,
+    symbol_chars: Vec<char>,
+}
+
+impl PronounceableGenerator {
+    /// Create a new pronounceable generator with the given constraints
+    pub fn new(constraints: PasswordConstraints) -> Result<Self, String> {
+        constraints.validate()?;
+
+        let filter = |s: &str, ambiguous: &[char]| -> Vec<char> {
+            s.chars()
+                .filter(|c| !constraints.exclude_ambiguous || !ambiguous.contains(c))
+                .collect()
+        };
+
+        let generator = Self {
+            consonants: filter(CONSONANTS, AMBIGUOUS_LETTERS),
+            vowels: filter(VOWELS, AMBIGUOUS_LETTERS),
+            numeric_chars: filter(NUMERIC, AMBIGUOUS_CHARS),
+            symbol_chars: filter(&constraints.symbols, AMBIGUOUS_CHARS),
+            constraints,
+        };
+
+        if generator.constraints.min_symbol.unwrap_or(0) > 0 && generator.symbol_chars.is_empty() {
+            return Err("Cannot add symbols: symbol set is empty".to_string());
+        }
+        if generator.constraints.min_hiragana.unwrap_or(0) > 0
+            || generator.constraints.min_katakana.unwrap_or(0) > 0
+        {
+            return Err(
+                "Pronounceable passwords are romanized and cannot contain kana".to_string(),
+            );
+        }
+
+        // The counts only grow with length, so checking both ends is enough
+        generator.counts(generator.constraints.min_length)?;
+        generator.counts(generator.constraints.max_length)?;
+
+        Ok(generator)
+    }
+
+    /// The shortest length from `min_length` to `max_length` whose
+    /// passwords carry at least `bits` of min-entropy
+    pub fn length_for_entropy(
+        constraints: &PasswordConstraints,
+        bits: f64,
+    ) -> Result<usize, String> {
+        constraints.validate()?;
+
+        for length in constraints.min_length..=constraints.max_length {
+            let mut constraints = constraints.clone();
+            constraints.min_length = length;
+            constraints.max_length = length;
+            if let Ok(generator) = Self::new(constraints)
+                && generator.entropy().minimum >= bits
+            {
+                return Ok(length);
+            }
+        }
+
+        Err(format!(
+            "No pronounceable password of {} to {} characters reaches {} bits of entropy",
+            constraints.min_length, constraints.max_length, bits
+        ))
+    }
+
+    /// Generate a pronounceable password satisfying the constraints
+    pub fn generate(&self) -> io::Result<String> {
+        self.generate_with(&mut SecureRandom)
+    }
+
+    /// Generate a pronounceable password using the given random source
+    pub fn generate_with(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        let length = if self.constraints.min_length == self.constraints.max_length {
+            self.constraints.min_length
+        } else {
+            let range = self.constraints.max_length - self.constraints.min_length + 1;
+            self.constraints.min_length + rng.random_range(range)?
+        };
+
+        let counts = self
+            .counts(length)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let mut letters = self.letters(counts.letters, rng)?;
+
+        // Capitalize a random subset of the letters
+        let mut positions: Vec<usize> = (0..letters.len()).collect();
+        rng.shuffle(&mut positions)?;
+        for &i in &positions[..counts.upper] {
+            letters[i] = letters[i].to_ascii_uppercase();
+        }
+
+        // Digits and symbols in random order, at random positions
+        let mut extras = Vec::with_capacity(counts.numeric + counts.symbol);
+        for _ in 0..counts.numeric {
+            extras.push(self.numeric_chars[rng.random_range(self.numeric_chars.len())?]);
+        }
+        for _ in 0..counts.symbol {
+            extras.push(self.symbol_chars[rng.random_range(self.symbol_chars.len())?]);
+        }
+        rng.shuffle(&mut extras)?;
+
+        let mut positions: Vec<usize> = (0..length).collect();
+        rng.shuffle(&mut positions)?;
+        let mut extra_positions = positions[..extras.len()].to_vec();
+        extra_positions.sort_unstable();
+
+        let mut password = String::with_capacity(length);
+        let mut letters = letters.into_iter();
+        let mut extras = extras.into_iter();
+        let mut next_extra = extra_positions.into_iter().peekable();
+        for i in 0..length {
+            if next_extra.next_if_eq(&i).is_some() {
+                password.extend(extras.next());
+            } else {
+                password.extend(letters.next());
+            }
+        }
+
+        Ok(password)
+    }
+
+    /// Entropy of the generated passwords
+    ///
+    /// Consonants and vowels are disjoint and the syllable rules below
+    /// never produce the same letters two ways, so every password
+    /// determines the choices that made it and the entropy is exact.
+    pub fn entropy(&self) -> Entropy {
+        let lengths = self.constraints.min_length..=self.constraints.max_length;
+        let length_bits = (lengths.clone().count() as f64).log2();
+        let letter_entropy = self.letter_entropy(self.constraints.max_length);
+
+        let mut total = 0.0;
+        let mut minimum = f64::INFINITY;
+        for length in lengths.clone() {
+            let Ok(counts) = self.counts(length) else {
+                continue;
+            };
+            let extras = counts.numeric + counts.symbol;
+            let fixed = log2_binomial(counts.letters, counts.upper)
+                + log2_binomial(length, extras)
+                + log2_binomial(extras, counts.numeric)
+                + counts.numeric as f64 * (self.numeric_chars.len() as f64).log2()
+                + counts.symbol as f64 * (self.symbol_chars.len() as f64).log2();
+
+            let letters = letter_entropy[counts.letters];
+            total += letters.average + fixed;
+            minimum = minimum.min(letters.minimum + fixed);
+        }
+
+        Entropy {
+            average: length_bits + total / lengths.count() as f64,
+            minimum: length_bits + minimum,
+        }
+    }
+
+    /// Decide the character counts for a password of the given length
+    fn counts(&self, length: usize) -> Result<Counts, String> {
+        let symbol_width = self.symbol_chars.iter().map(|c| c.len_utf8()).max();
+        let numeric = self.constraints.min_numeric.unwrap_or(0);
+        let symbol = self.constraints.min_symbol.unwrap_or(0);
+        let letters = length.saturating_sub(numeric + symbol);
+
+        // Capitalize enough letters to stay within the lowercase maximum
+        let max_lower = self.constraints.max_lower.unwrap_or(letters);
+        let upper = self
+            .constraints
+            .min_upper
+            .unwrap_or(0)
+            .max(letters.saturating_sub(max_lower));
+
+        if self.constraints.max_upper.is_some_and(|max| upper > max) {
+            return Err(format!(
+                "A pronounceable password of length {} needs at least {} uppercase letters, which exceeds max_upper",
+                length, upper
+            ));
+        }
+
+        // Everything but the symbols is ASCII
+        let bytes = length - symbol + symbol * symbol_width.unwrap_or(1);
+        if self.constraints.max_bytes.is_some_and(|max| bytes > max) {
+            return Err(format!(
+                "A pronounceable password of length {} can take {} bytes, which exceeds max_bytes",
+                length, bytes
+            ));
+        }
+
+        Ok(Counts {
+            letters,
+            upper,
+            numeric,
+            symbol,
+        })
+    }
+
+    /// Generate lowercase letters as a sequence of CV and CVC syllables
+    ///
+    /// A single letter is a vowel. Otherwise the final syllables are
+    /// forced so that no letter is ever left over: two or four letters
+    /// left become CV syllables and three become CVC.
+    fn letters(&self, count: usize, rng: &mut dyn RandomSource) -> io::Result<Vec<char>> {
+        let mut letters = Vec::with_capacity(count);
+        if count == 1 {
+            letters.push(self.vowels[rng.random_range(self.vowels.len())?]);
+            return Ok(letters);
+        }
+
+        let mut remaining = count;
+        while remaining > 0 {
+            let syllable = match remaining {
+                2 | 4 => 2,
+                3 => 3,
+                _ => 2 + rng.random_range(2)?,
+            };
+
+            letters.push(self.consonants[rng.random_range(self.consonants.len())?]);
+            letters.push(self.vowels[rng.random_range(self.vowels.len())?]);
+            if syllable == 3 {
+                letters.push(self.consonants[rng.random_range(self.consonants.len())?]);
+            }
+            remaining -= syllable;
+        }
+
+        Ok(letters)
+    }
+
+    /// Entropy of `letters()` for every count up to `max`
+    fn letter_entropy(&self, max: usize) -> Vec<Entropy> {
+        let consonant = (self.consonants.len() as f64).log2();
+        let vowel = (self.vowels.len() as f64).log2();
+        let cv = consonant + vowel;
+        let cvc = 2.0 * consonant + vowel;
+
+        let mut table = Vec::with_capacity(max + 1);
+        for count in 0..=max {
+            let fixed = |bits: f64| Entropy {
+                average: bits,
+                minimum: bits,
+            };
+            let entry = match count {
+                0 => fixed(0.0),
+                1 => fixed(vowel),
+                2 => fixed(cv),
+                3 => fixed(cvc),
+                4 => fixed(2.0 * cv),
+                _ => {
+                    // One bit picks the syllable shape, then the rest follows
+                    let short: Entropy = table[count - 2];
+                    let long: Entropy = table[count - 3];
+                    Entropy {
+                        average: 1.0 + 0.5 * (cv + short.average) + 0.5 * (cvc + long.average),
+                        minimum: 1.0 + (cv + short.minimum).min(cvc + long.minimum),
+                    }
+                }
+            };
+            table.push(entry);
+        }
+
+        table
+    }
+}
+
+/// log2 of the binomial coefficient n choose k
+fn log2_binomial(n: usize, k: usize) -> f64 {
+    (0..k.min(n))
+        .map(|i| ((n - i) as f64 / (i + 1) as f64).log2())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraints(length: usize) -> PasswordConstraints {
+        PasswordConstraints {
+            min_numeric: None,
+            max_numeric: None,
+            min_lower: None,
+            max_lower: None,
+            min_upper: None,
+            max_upper: None,
+            min_symbol: None,
+            max_symbol: None,
+            min_hiragana: None,
+            max_hiragana: None,
+            min_katakana: None,
+            max_katakana: None,
+            min_length: length,
+            max_length: length,
+            max_bytes: None,
+            symbols: "!@#$".to_string(),
+            exclude_ambiguous: false,
+        }
+    }
+
+    /// Whether a string is made of CV and CVC syllables
+    fn is_syllabic(letters: &str) -> bool {
+        let pattern: String = letters
+            .chars()
+            .map(|c| if VOWELS.contains(c) { 'V' } else { 'C' })
+            .collect();
+        let mut rest = pattern.as_str();
+        while !rest.is_empty() {
+            rest = match rest.strip_prefix("CV") {
+                Some(after) if after.starts_with("CV") || after.is_empty() => after,
+                Some(after) => match after.strip_prefix('C') {
+                    Some(after) => after,
+                    None => return false,
+                },
+                None => return false,
+            };
+        }
+        true
+    }
+
+    #[test]
+    fn test_plain_pronounceable() {
+        let generator = PronounceableGenerator::new(constraints(14)).unwrap();
+        for _ in 0..50 {
+            let password = generator.generate().unwrap();
+            assert_eq!(password.len(), 14);
+            assert!(password.chars().all(|c| c.is_ascii_lowercase()));
+            assert!(is_syllabic(&password), "{}", password);
+        }
+    }
+
+    #[test]
+    fn test_respects_class_minimums() {
+        let mut c = constraints(16);
+        c.min_numeric = Some(2);
+        c.min_symbol = Some(1);
+        c.min_upper = Some(3);
+        let generator = PronounceableGenerator::new(c).unwrap();
+        let password = generator.generate().unwrap();
+
+        assert_eq!(password.len(), 16);
+        assert_eq!(password.chars().filter(|c| c.is_ascii_digit()).count(), 2);
+        assert_eq!(password.chars().filter(|c| "!@#$".contains(*c)).count(), 1);
+        assert_eq!(
+            password.chars().filter(|c| c.is_ascii_uppercase()).count(),
+            3
+        );
+
+        let letters: String = password
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        assert!(is_syllabic(&letters), "{}", password);
+    }
+
+    #[test]
+    fn test_max_lower_forces_uppercase() {
+        let mut c = constraints(10);
+        c.max_lower = Some(6);
+        let generator = PronounceableGenerator::new(c.clone()).unwrap();
+        let password = generator.generate().unwrap();
+        assert_eq!(
+            password.chars().filter(|c| c.is_ascii_uppercase()).count(),
+            4
+        );
+
+        c.max_upper = Some(3);
+        assert!(PronounceableGenerator::new(c).is_err());
+    }
+
+    #[test]
+    fn test_exclude_ambiguous() {
+        let mut c = constraints(20);
+        c.min_upper = Some(5);
+        c.min_numeric = Some(3);
+        c.exclude_ambiguous = true;
+        let generator = PronounceableGenerator::new(c).unwrap();
+        let password = generator.generate().unwrap();
+        for ch in password.chars() {
+            assert!(!"0O1lI|io".contains(ch), "{}", password);
+        }
+    }
+
+    #[test]
+    fn test_entropy_small_cases() {
+        // Two letters: one consonant and one vowel
+        let generator = PronounceableGenerator::new(constraints(2)).unwrap();
+        let expected = 17f64.log2() + 5f64.log2();
+        let entropy = generator.entropy();
+        assert!((entropy.average - expected).abs() < 1e-9);
+        assert!((entropy.minimum - expected).abs() < 1e-9);
+
+        // Five letters: CV+CVC or CVC+CV, chosen by one bit
+        let generator = PronounceableGenerator::new(constraints(5)).unwrap();
+        let expected = 1.0 + 3.0 * 17f64.log2() + 2.0 * 5f64.log2();
+        let entropy = generator.entropy();
+        assert!((entropy.average - expected).abs() < 1e-9);
+        assert!((entropy.minimum - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entropy_is_below_random_password() {
+        let generator = PronounceableGenerator::new(constraints(16)).unwrap();
+        let entropy = generator.entropy();
+        assert!(entropy.minimum <= entropy.average);
+        assert!(entropy.average < 16.0 * 26f64.log2());
+        assert!(entropy.average > 16.0 * 3.0);
+    }
+
+    #[test]
+    fn test_length_for_entropy() {
+        let mut c = constraints(1);
+        c.max_length = 64;
+        let length = PronounceableGenerator::length_for_entropy(&c, 60.0).unwrap();
+
+        c.min_length = length;
+        c.max_length = length;
+        assert!(
+            PronounceableGenerator::new(c.clone())
+                .unwrap()
+                .entropy()
+                .minimum
+                >= 60.0
+        );
+        c.min_length = length - 1;
+        c.max_length = length - 1;
+        assert!(
+            PronounceableGenerator::new(c.clone())
+                .unwrap()
+                .entropy()
+                .minimum
+                < 60.0
+        );
+
+        c.min_length = 1;
+        c.max_length = 8;
+        assert!(PronounceableGenerator::length_for_entropy(&c, 60.0).is_err());
+    }
+
+    #[test]
+    fn test_log2_binomial() {
+        assert!((log2_binomial(5, 2) - 10f64.log2()).abs() < 1e-9);
+        assert_eq!(log2_binomial(4, 0), 0.0);
+        assert!((log2_binomial(4, 4)).abs() < 1e-9);
+    }
+}
@@ -1,35 +1,67 @@
+use crate::health::HealthTests;
+use crate::sha256::Sha256;
 use std::fs::File;
 use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, Once, OnceLock};
 
-/// A zero-dependency secure random number generator
+/// User-supplied entropy, condensed into a single SHA-256 digest
+static EXTRA_ENTROPY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+/// The /dev/urandom handle, opened on first use and kept open, for
+/// systems without the getrandom syscall
+static URANDOM: OnceLock<File> = OnceLock::new();
+
+/// Continuous health tests on every byte read from the OS
+static OS_HEALTH: Mutex<HealthTests> = Mutex::new(HealthTests::new());
+
+/// The process-wide DRBG behind `SecureRandom`, seeded on first use
+static DRBG: Mutex<Option<ChaChaRng>> = Mutex::new(None);
+
+/// Incremented in the child after every fork
 ///
-/// Uses /dev/urandom on Unix-like systems for cryptographically secure random numbers
-pub struct SecureRandom;
+/// A forked child starts with a copy of its parent's DRBG, which would
+/// hand both processes the same bytes.
+static FORK_GENERATION: AtomicU64 = AtomicU64::new(0);
 
-impl SecureRandom {
-    /// Fill a buffer with cryptographically secure random bytes
-    ///
-    /// # Errors
-    /// Returns an error if unable to read from the system's secure random source
-    pub fn fill_bytes(buf: &mut [u8]) -> io::Result<()> {
-        #[cfg(unix)]
-        {
-            let mut file = File::open("/dev/urandom")?;
-            file.read_exact(buf)?;
-            Ok(())
+/// Register the fork handler that bumps `FORK_GENERATION`
+fn watch_forks() {
+    static REGISTER: Once = Once::new();
+
+    #[cfg(unix)]
+    REGISTER.call_once(|| {
+        unsafe extern "C" {
+            fn pthread_atfork(
+                prepare: Option<extern "C" fn()>,
+                parent: Option<extern "C" fn()>,
+                child: Option<extern "C" fn()>,
+            ) -> i32;
         }
 
-        #[cfg(not(unix))]
-        {
-            // TODO: Implement Windows support using BCryptGenRandom
-            compile_error!("Windows support not yet implemented. Please use Unix-like systems for now.");
+        extern "C" fn child() {
+            FORK_GENERATION.fetch_add(1, Ordering::SeqCst);
         }
-    }
+
+        // SAFETY: the handler only touches an atomic, which is safe to do
+        // between fork and exec
+        unsafe {
+            pthread_atfork(None, None, Some(child));
+        }
+    });
+}
+
+/// A source of random bytes for the generators
+///
+/// Generators take the source as a parameter, so tests can swap the OS
+/// randomness for a reproducible [`SeededRandom`].
+pub trait RandomSource {
+    /// Fill a buffer with random bytes
+    fn fill_bytes(&mut self, buf: &mut [u8]) -> io::Result<()>;
 
     /// Generate a random number in the range [0, max)
     ///
     /// Uses rejection sampling to avoid modulo bias
-    pub fn random_range(max: usize) -> io::Result<usize> {
+    fn random_range(&mut self, max: usize) -> io::Result<usize> {
         if max == 0 {
             return Ok(0);
         }
@@ -42,7 +74,7 @@ impl SecureRandom {
 
         loop {
             let mut buf = [0u8; 4];
-            Self::fill_bytes(&mut buf)?;
+            self.fill_bytes(&mut buf)?;
             let value = u32::from_le_bytes(buf);
 
             // Reject values outside the range to avoid modulo bias
@@ -51,26 +83,378 @@ impl SecureRandom {
             }
         }
     }
+}
 
+impl dyn RandomSource + '_ {
     /// Shuffle a slice in place using Fisher-Yates algorithm
-    pub fn shuffle<T>(slice: &mut [T]) -> io::Result<()> {
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) -> io::Result<()> {
         let len = slice.len();
         for i in (1..len).rev() {
-            let j = Self::random_range(i + 1)?;
+            let j = self.random_range(i + 1)?;
             slice.swap(i, j);
         }
         Ok(())
     }
 }
 
+/// A zero-dependency secure random number generator
+///
+/// Draws from a process-wide [`ChaChaRng`] seeded by the OS, so
+/// every request after the first is served without a syscall.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SecureRandom;
+
+impl SecureRandom {
+    /// Mix additional entropy (keyboard mashing, coin flips, a file...) into
+    /// all subsequent output
+    ///
+    /// The data is hashed into a pool together with anything added before.
+    /// Every DRBG seed is fresh OS randomness XORed with a stream keyed by
+    /// the pool, so weak or even attacker-chosen input can never make the
+    /// output worse than the OS source alone.
+    pub fn add_entropy(data: &[u8]) {
+        {
+            let mut pool = EXTRA_ENTROPY.lock().unwrap_or_else(|e| e.into_inner());
+
+            let mut hasher = Sha256::new();
+            hasher.update(b"genpass extra entropy");
+            if let Some(ref previous) = *pool {
+                hasher.update(previous);
+            }
+            hasher.update(data);
+
+            *pool = Some(hasher.finalize());
+        }
+
+        // Reseed before the next request so the new entropy takes effect.
+        // The pool lock is released first: reseeding locks the pool while
+        // holding the DRBG, so holding both here could deadlock.
+        *DRBG.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Fill a buffer straight from the operating system's random source,
+    /// after the continuous health tests have passed it
+    pub(crate) fn fill_os_bytes(buf: &mut [u8]) -> io::Result<()> {
+        Self::read_os_bytes(buf)?;
+        OS_HEALTH
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .check(buf)
+    }
+
+    /// Read the operating system's random source
+    ///
+    /// Linux uses the getrandom syscall, which needs no device node and
+    /// blocks until the kernel pool is initialized. /dev/urandom is the
+    /// fallback elsewhere and where the syscall is missing or filtered.
+    fn read_os_bytes(buf: &mut [u8]) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            #[cfg(target_os = "linux")]
+            let syscall_error = match getrandom_fill(buf) {
+                Ok(()) => return Ok(()),
+                Err(e) => Some(e),
+            };
+            #[cfg(not(target_os = "linux"))]
+            let syscall_error: Option<io::Error> = None;
+
+            urandom_fill(buf).map_err(|e| {
+                let message = match syscall_error {
+                    Some(syscall_error) => format!(
+                        "no OS randomness available: getrandom failed ({}) and /dev/urandom could not be read ({})",
+                        syscall_error, e
+                    ),
+                    None => format!("could not read /dev/urandom: {}", e),
+                };
+                io::Error::new(e.kind(), message)
+            })
+        }
+
+        #[cfg(not(unix))]
+        {
+            // TODO: Implement Windows support using BCryptGenRandom
+            compile_error!("Windows support not yet implemented. Please use Unix-like systems for now.");
+        }
+    }
+}
+
+/// Fill a buffer with the getrandom syscall, retrying interrupted and
+/// short reads
+#[cfg(target_os = "linux")]
+fn getrandom_fill(buf: &mut [u8]) -> io::Result<()> {
+    unsafe extern "C" {
+        fn getrandom(buf: *mut u8, buflen: usize, flags: u32) -> isize;
+    }
+
+    let mut filled = 0;
+    while filled < buf.len() {
+        let rest = &mut buf[filled..];
+        // SAFETY: the kernel writes at most `rest.len()` bytes into `rest`
+        let n = unsafe { getrandom(rest.as_mut_ptr(), rest.len(), 0) };
+        if n < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(e);
+        }
+        filled += n as usize;
+    }
+    Ok(())
+}
+
+/// Fill a buffer from /dev/urandom, opening it on first use
+#[cfg(unix)]
+fn urandom_fill(buf: &mut [u8]) -> io::Result<()> {
+    let mut file = match URANDOM.get() {
+        Some(file) => file,
+        None => {
+            let file = File::open("/dev/urandom")?;
+            URANDOM.get_or_init(|| file)
+        }
+    };
+    file.read_exact(buf)
+}
+
+impl RandomSource for SecureRandom {
+    /// Fill a buffer with cryptographically secure random bytes
+    ///
+    /// # Errors
+    /// Returns an error if unable to read from the system's secure random source
+    fn fill_bytes(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let mut drbg = DRBG.lock().unwrap_or_else(|e| e.into_inner());
+        let drbg = match *drbg {
+            Some(ref mut drbg) => drbg,
+            None => drbg.insert(ChaChaRng::new()?),
+        };
+        drbg.fill_bytes(buf)
+    }
+}
+
+/// ChaCha20 blocks generated per refill; the first 32 bytes become the next key
+const DRBG_BLOCKS: usize = 8;
+
+/// Output after which the DRBG pulls fresh randomness from the OS
+const RESEED_INTERVAL: usize = 1 << 20;
+
+/// A ChaCha20 deterministic random bit generator seeded from the OS
+///
+/// Each refill runs ChaCha20 under the current key and immediately
+/// replaces the key with the first 32 bytes of the output, so a later
+/// compromise of the state reveals nothing already handed out. Fresh OS
+/// randomness is mixed into the key every `RESEED_INTERVAL` bytes and
+/// after a fork. The output runs through the SP 800-90B health tests, and
+/// the generator refuses to continue once they trip.
+pub struct ChaChaRng {
+    key: [u8; 32],
+    buf: [u8; 64 * DRBG_BLOCKS],
+    /// Start of the unused output; `buf.len()` when empty
+    pos: usize,
+    /// Output produced since the last reseed
+    produced: usize,
+    /// `FORK_GENERATION` when last seeded
+    fork_generation: u64,
+    health: HealthTests,
+}
+
+impl ChaChaRng {
+    /// A generator seeded from the OS and any extra entropy
+    pub fn new() -> io::Result<Self> {
+        watch_forks();
+
+        let mut rng = ChaChaRng {
+            key: [0; 32],
+            buf: [0; 64 * DRBG_BLOCKS],
+            pos: 64 * DRBG_BLOCKS,
+            produced: 0,
+            fork_generation: 0,
+            health: HealthTests::new(),
+        };
+        rng.reseed()?;
+        Ok(rng)
+    }
+
+    /// Mix fresh OS randomness and the extra entropy pool into the key,
+    /// discarding any buffered output
+    fn reseed(&mut self) -> io::Result<()> {
+        let mut seed = [0u8; 32];
+        SecureRandom::fill_os_bytes(&mut seed)?;
+
+        let pool = *EXTRA_ENTROPY.lock().unwrap_or_else(|e| e.into_inner());
+        self.key = Self::mix_seed(&seed, &self.key, pool.as_ref());
+        seed.fill(0);
+
+        self.buf.fill(0);
+        self.pos = self.buf.len();
+        self.produced = 0;
+        self.fork_generation = FORK_GENERATION.load(Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// The next key: the OS seed XORed with a stream keyed by the old key
+    /// and the extra entropy pool
+    ///
+    /// The stream never depends on the OS bytes, so the XOR passes them
+    /// through unchanged in strength: the key is at least as unpredictable
+    /// as the OS seed, whatever the pool holds. Hashing the OS bytes into
+    /// the key instead would only be as strong as the hash.
+    fn mix_seed(seed: &[u8; 32], key: &[u8; 32], pool: Option<&[u8; 32]>) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"genpass chacha20 drbg");
+        hasher.update(key);
+        if let Some(pool) = pool {
+            hasher.update(pool);
+        }
+
+        let mut mixed = hasher.finalize();
+        for (byte, os_byte) in mixed.iter_mut().zip(seed) {
+            *byte ^= os_byte;
+        }
+        mixed
+    }
+
+    /// Run ChaCha20 to refill the buffer, erasing the key it used
+    fn refill(&mut self) {
+        let nonce = [0u8; 12];
+        for (counter, block) in self.buf.chunks_exact_mut(64).enumerate() {
+            block.copy_from_slice(&chacha20_block(&self.key, counter as u32, &nonce));
+        }
+        self.key.copy_from_slice(&self.buf[..32]);
+        self.buf[..32].fill(0);
+        self.pos = 32;
+    }
+}
+
+impl RandomSource for ChaChaRng {
+    fn fill_bytes(&mut self, out: &mut [u8]) -> io::Result<()> {
+        if self.fork_generation != FORK_GENERATION.load(Ordering::SeqCst) {
+            self.reseed()?;
+        }
+
+        let mut filled = 0;
+        while filled < out.len() {
+            if self.pos == self.buf.len() {
+                if self.produced >= RESEED_INTERVAL {
+                    self.reseed()?;
+                }
+                self.refill();
+            }
+
+            // Bytes are erased once handed out, so no two requests share them
+            let n = (out.len() - filled).min(self.buf.len() - self.pos);
+            let unused = &mut self.buf[self.pos..self.pos + n];
+            out[filled..filled + n].copy_from_slice(unused);
+            unused.fill(0);
+            self.pos += n;
+            self.produced += n;
+            filled += n;
+        }
+        self.health.check(out)
+    }
+}
+
+/// "expand 32-byte k"
+const CHACHA_CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// The ChaCha quarter round on four words of the state (RFC 8439, 2.1)
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// One 64-byte block of ChaCha20 keystream (RFC 8439, 2.3)
+pub(crate) fn chacha20_block(key: &[u8; 32], counter: u32, nonce: &[u8; 12]) -> [u8; 64] {
+    let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().expect("4-byte word"));
+
+    let mut initial = [0u32; 16];
+    initial[..4].copy_from_slice(&CHACHA_CONSTANTS);
+    for (i, chunk) in key.chunks_exact(4).enumerate() {
+        initial[4 + i] = word(chunk);
+    }
+    initial[12] = counter;
+    for (i, chunk) in nonce.chunks_exact(4).enumerate() {
+        initial[13 + i] = word(chunk);
+    }
+
+    // 20 rounds: alternating column and diagonal rounds
+    let mut state = initial;
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    let mut out = [0u8; 64];
+    for (i, chunk) in out.chunks_exact_mut(4).enumerate() {
+        chunk.copy_from_slice(&state[i].wrapping_add(initial[i]).to_le_bytes());
+    }
+    out
+}
+
+/// A deterministic random source for tests and bug reports
+///
+/// **Insecure:** anyone who knows the seed can reproduce every password.
+/// The output is SHA-256 of the seed and a block counter, so the same seed
+/// gives the same passwords on every platform and release.
+#[derive(Debug, Clone)]
+pub struct SeededRandom {
+    seed: u64,
+    counter: u64,
+    block: [u8; 32],
+    used: usize,
+}
+
+impl SeededRandom {
+    /// A source that replays the same bytes for the same seed
+    pub fn new(seed: u64) -> Self {
+        SeededRandom {
+            seed,
+            counter: 0,
+            block: [0; 32],
+            used: 32,
+        }
+    }
+}
+
+impl RandomSource for SeededRandom {
+    fn fill_bytes(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        for byte in buf {
+            if self.used == self.block.len() {
+                let mut hasher = Sha256::new();
+                hasher.update(b"genpass insecure seed");
+                hasher.update(&self.seed.to_le_bytes());
+                hasher.update(&self.counter.to_le_bytes());
+                self.block = hasher.finalize();
+                self.counter += 1;
+                self.used = 0;
+            }
+            *byte = self.block[self.used];
+            self.used += 1;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::stats;
 
     #[test]
     fn test_fill_bytes() {
         let mut buf = [0u8; 32];
-        SecureRandom::fill_bytes(&mut buf).unwrap();
+        SecureRandom.fill_bytes(&mut buf).unwrap();
 
         // Check that not all bytes are zero (extremely unlikely with random data)
         assert!(buf.iter().any(|&b| b != 0));
@@ -80,7 +464,7 @@ mod tests {
     fn test_random_range() {
         // Test basic functionality
         for _ in 0..100 {
-            let value = SecureRandom::random_range(10).unwrap();
+            let value = SecureRandom.random_range(10).unwrap();
             assert!(value < 10);
         }
     }
@@ -88,10 +472,69 @@ mod tests {
     #[test]
     fn test_random_range_edge_cases() {
         // Test edge case: max = 0
-        assert_eq!(SecureRandom::random_range(0).unwrap(), 0);
+        assert_eq!(SecureRandom.random_range(0).unwrap(), 0);
 
         // Test edge case: max = 1
-        assert_eq!(SecureRandom::random_range(1).unwrap(), 0);
+        assert_eq!(SecureRandom.random_range(1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_add_entropy() {
+        SecureRandom::add_entropy(b"heads tails tails heads");
+        assert!(EXTRA_ENTROPY.lock().unwrap().is_some());
+
+        // Output still varies: the OS source is always used
+        let mut a = [0u8; 48];
+        let mut b = [0u8; 48];
+        SecureRandom.fill_bytes(&mut a).unwrap();
+        SecureRandom.fill_bytes(&mut b).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_extra_entropy_never_weakens_os_seed() {
+        // Whatever the pool and old key hold, the new key is the OS seed
+        // XORed with a fixed stream, so distinct seeds give distinct keys
+        let key = [7u8; 32];
+        let pool = [0u8; 32];
+        let zero = ChaChaRng::mix_seed(&[0; 32], &key, Some(&pool));
+        for seed in [[1u8; 32], [0x5a; 32], [0xff; 32]] {
+            let mixed = ChaChaRng::mix_seed(&seed, &key, Some(&pool));
+            let stream: Vec<u8> = mixed.iter().zip(&seed).map(|(m, s)| m ^ s).collect();
+            assert_eq!(stream, zero);
+        }
+        assert_ne!(zero, ChaChaRng::mix_seed(&[0; 32], &key, None));
+    }
+
+    #[test]
+    fn test_add_entropy_while_generating() {
+        // Each call to add_entropy forces the generating thread to reseed,
+        // which takes the two locks in the opposite order
+        let (done, finished) = std::sync::mpsc::channel();
+        let adder = {
+            let done = done.clone();
+            std::thread::spawn(move || {
+                for i in 0u32..2000 {
+                    SecureRandom::add_entropy(&i.to_le_bytes());
+                }
+                done.send(()).unwrap();
+            })
+        };
+        let generator = std::thread::spawn(move || {
+            let mut buf = [0u8; 16];
+            for _ in 0..2000 {
+                SecureRandom.fill_bytes(&mut buf).unwrap();
+            }
+            done.send(()).unwrap();
+        });
+
+        for _ in 0..2 {
+            finished
+                .recv_timeout(std::time::Duration::from_secs(60))
+                .expect("add_entropy and fill_bytes deadlocked");
+        }
+        adder.join().unwrap();
+        generator.join().unwrap();
     }
 
     #[test]
@@ -99,11 +542,183 @@ mod tests {
         let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
         let original = data.clone();
 
-        SecureRandom::shuffle(&mut data).unwrap();
+        let rng: &mut dyn RandomSource = &mut SecureRandom;
+        rng.shuffle(&mut data).unwrap();
 
         // Check that all elements are still present
         let mut sorted_data = data.clone();
         sorted_data.sort();
         assert_eq!(sorted_data, original);
     }
+
+    #[test]
+    fn test_seeded_random() {
+        let mut a = [0u8; 40];
+        let mut b = [0u8; 40];
+        SeededRandom::new(7).fill_bytes(&mut a).unwrap();
+
+        // Reading in pieces gives the same stream
+        let mut rng = SeededRandom::new(7);
+        rng.fill_bytes(&mut b[..5]).unwrap();
+        rng.fill_bytes(&mut b[5..]).unwrap();
+        assert_eq!(a, b);
+
+        SeededRandom::new(8).fill_bytes(&mut b).unwrap();
+        assert_ne!(a, b);
+    }
+
+    fn unhex(s: &str) -> Vec<u8> {
+        let digits: Vec<u8> = s.bytes().filter(|b| b.is_ascii_hexdigit()).collect();
+        digits
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_chacha20_quarter_round() {
+        // RFC 8439, 2.1.1
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&[0x11111111, 0x01020304, 0x9b8d6f43, 0x01234567]);
+        quarter_round(&mut state, 0, 1, 2, 3);
+        assert_eq!(state[..4], [0xea2a92f4, 0xcb1cf8ce, 0x4581472e, 0x5881c4bb]);
+    }
+
+    #[test]
+    fn test_chacha20_block() {
+        // RFC 8439, 2.3.2
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce: [u8; 12] = unhex("000000090000004a00000000").try_into().unwrap();
+        let expected = unhex(
+            "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e
+             d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e",
+        );
+        assert_eq!(chacha20_block(&key, 1, &nonce).to_vec(), expected);
+
+        // RFC 8439, A.1 test vector #1: all zeros
+        let expected = unhex(
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7
+             da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586",
+        );
+        assert_eq!(chacha20_block(&[0; 32], 0, &[0; 12]).to_vec(), expected);
+    }
+
+    #[test]
+    fn test_chacha20_encryption() {
+        // RFC 8439, 2.4.2: the keystream XORed with the plaintext
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce: [u8; 12] = unhex("000000000000004a00000000").try_into().unwrap();
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you \
+only one tip for the future, sunscreen would be it.";
+        let expected = unhex(
+            "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b
+             f91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d8
+             07ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab7793736
+             5af90bbf74a35be6b40b8eedf2785e42874d",
+        );
+
+        let ciphertext: Vec<u8> = plaintext
+            .chunks(64)
+            .enumerate()
+            .flat_map(|(i, chunk)| {
+                let block = chacha20_block(&key, 1 + i as u32, &nonce);
+                chunk
+                    .iter()
+                    .zip(block)
+                    .map(|(p, k)| p ^ k)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(ciphertext, expected);
+    }
+
+    #[test]
+    fn test_chacha_rng_key_erasure() {
+        let mut rng = ChaChaRng::new().unwrap();
+        let key = rng.key;
+        let mut out = [0u8; 100];
+        rng.fill_bytes(&mut out).unwrap();
+
+        // The refill replaced the key and erased what it handed out
+        assert_ne!(rng.key, key);
+        assert_eq!(rng.pos, 132);
+        assert!(rng.buf[..rng.pos].iter().all(|&b| b == 0));
+
+        // Requests larger than the buffer span several refills
+        let mut large = vec![0u8; 5000];
+        rng.fill_bytes(&mut large).unwrap();
+        assert!(large[4000..].iter().any(|&b| b != 0));
+    }
+
+    #[test]
+    fn test_chacha_rng_reseeds() {
+        let mut rng = ChaChaRng::new().unwrap();
+        let mut out = [0u8; 8];
+        rng.fill_bytes(&mut out).unwrap();
+
+        // Periodic reseed once the interval is used up
+        rng.produced = RESEED_INTERVAL;
+        rng.pos = rng.buf.len();
+        rng.fill_bytes(&mut out).unwrap();
+        assert_eq!(rng.produced, 8);
+
+        // A fork throws away the buffered output
+        rng.fill_bytes(&mut out).unwrap();
+        FORK_GENERATION.fetch_add(1, Ordering::SeqCst);
+        rng.fill_bytes(&mut out).unwrap();
+        assert_eq!(rng.pos, 40);
+        assert_eq!(rng.fork_generation, FORK_GENERATION.load(Ordering::SeqCst));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_getrandom() {
+        // Large requests may come back in pieces
+        let mut buf = vec![0u8; 1 << 20];
+        getrandom_fill(&mut buf).unwrap();
+        assert!(buf[buf.len() - 64..].iter().any(|&b| b != 0));
+
+        let mut empty = [0u8; 0];
+        getrandom_fill(&mut empty).unwrap();
+    }
+
+    #[test]
+    fn test_urandom_fallback() {
+        let mut buf = [0u8; 64];
+        urandom_fill(&mut buf).unwrap();
+        assert!(buf.iter().any(|&b| b != 0));
+    }
+
+    #[test]
+    fn test_random_range_is_unbiased() {
+        let mut rng = SeededRandom::new(3);
+        for modulus in [3, 7, 10, 100, 255, 1000] {
+            let mut counts = vec![0u64; modulus];
+            for _ in 0..modulus * 50 {
+                counts[rng.random_range(modulus).unwrap()] += 1;
+            }
+            stats::assert_uniform(&format!("random_range({})", modulus), &counts);
+        }
+
+        // Without rejection, the first third of this range would come up
+        // twice as often as the others
+        let modulus = 3 << 30;
+        let mut thirds = [0u64; 3];
+        for _ in 0..30_000 {
+            thirds[rng.random_range(modulus).unwrap() >> 30] += 1;
+        }
+        stats::assert_uniform("random_range(3 << 30)", &thirds);
+    }
+
+    #[test]
+    fn test_shuffle_permutations_are_uniform() {
+        let rng: &mut dyn RandomSource = &mut SeededRandom::new(4);
+        let mut counts = vec![0u64; 24];
+        for _ in 0..24_000 {
+            let mut items = [0, 1, 2, 3];
+            rng.shuffle(&mut items).unwrap();
+            counts[stats::permutation_index(&items)] += 1;
+        }
+        stats::assert_uniform("shuffle of 4 items", &counts);
+    }
 }
@@ -0,0 +1,739 @@
+use crate::bigint::BigUint;
+use crate::random::{RandomSource, SecureRandom};
+use std::io;
+
+/// Longest string a pattern may produce
+const MAX_LENGTH: usize = 1024;
+
+/// Candidates drawn before giving up on the lookaheads and anchors
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// Candidates drawn to estimate how often the lookaheads hold
+const ESTIMATE_SAMPLES: usize = 1000;
+
+/// The alphabet for `.` and negated classes: printable ASCII
+fn printable() -> Vec<char> {
+    (' '..='~').collect()
+}
+
+/// One node of a parsed pattern
+#[derive(Debug, Clone)]
+enum Node {
+    /// One character from a sorted, non-empty set
+    Set(Vec<char>),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+    Start,
+    End,
+    Lookahead {
+        negative: bool,
+        node: Box<Node>,
+    },
+}
+
+/// Strings matching a password-policy regex
+///
+/// Supports literals, `.`, `\d` `\w` `\s` and their negations, classes
+/// such as `[a-z0-9_-]` and `[^"']`, groups, `|`, `?`, `{n}`, `{n,m}`, the
+/// anchors `^` and `$`, and lookaheads `(?=...)` and `(?!...)`. Outside
+/// lookaheads every repetition must be bounded, so `*`, `+` and `{n,}` are
+/// rejected. Characters are limited to printable ASCII.
+///
+/// The pattern is sampled as if lookaheads and anchors were absent, and
+/// candidates that fail them are redrawn.
+#[derive(Debug, Clone)]
+pub struct RegexGenerator {
+    root: Node,
+    /// Whether candidates must be checked against lookaheads or inner anchors
+    constrained: bool,
+    keyspace: BigUint,
+}
+
+impl RegexGenerator {
+    /// Parse a pattern
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            lookahead_depth: 0,
+        };
+        let root = parser.parse_alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("unmatched ')' at position {}", parser.pos + 1));
+        }
+
+        if max_length(&root) > MAX_LENGTH {
+            return Err(format!(
+                "pattern can produce strings longer than {} characters",
+                MAX_LENGTH
+            ));
+        }
+
+        let constrained = match &root {
+            Node::Alternation(branches) => !branches.iter().all(branch_is_free),
+            other => !branch_is_free(other),
+        };
+        let keyspace = count(&root);
+
+        Ok(Self {
+            root,
+            constrained,
+            keyspace,
+        })
+    }
+
+    /// Number of ways to fill in the pattern, ignoring lookaheads
+    ///
+    /// Ambiguous patterns such as `(a|a)` count some strings more than
+    /// once, so this is an upper bound on distinct matches.
+    pub fn keyspace(&self) -> &BigUint {
+        &self.keyspace
+    }
+
+    /// Whether lookaheads or anchors narrow down the keyspace
+    pub fn is_constrained(&self) -> bool {
+        self.constrained
+    }
+
+    /// Estimated entropy of a generated string in bits
+    ///
+    /// Exact for unconstrained patterns. Otherwise the keyspace is scaled
+    /// by the fraction of sampled candidates that pass the lookaheads.
+    pub fn estimate_entropy(&self) -> io::Result<f64> {
+        if !self.constrained {
+            return Ok(self.keyspace.log2());
+        }
+
+        let mut accepted = 0;
+        for _ in 0..ESTIMATE_SAMPLES {
+            if self.is_match(&self.sample(&mut SecureRandom)?) {
+                accepted += 1;
+            }
+        }
+        if accepted == 0 {
+            return Err(unsatisfiable());
+        }
+        Ok(self.keyspace.log2() + (accepted as f64 / ESTIMATE_SAMPLES as f64).log2())
+    }
+
+    /// Whether a string matches the whole pattern, lookaheads included
+    pub fn is_match(&self, input: &str) -> bool {
+        let input: Vec<char> = input.chars().collect();
+        matches(&self.root, &input, 0, &mut |end| end == input.len())
+    }
+
+    /// Generate a matching string
+    pub fn generate(&self) -> io::Result<String> {
+        self.generate_with(&mut SecureRandom)
+    }
+
+    /// Generate a matching string using the given random source
+    pub fn generate_with(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        for _ in 0..MAX_ATTEMPTS {
+            let candidate = self.sample(rng)?;
+            if !self.constrained || self.is_match(&candidate) {
+                return Ok(candidate);
+            }
+        }
+        Err(unsatisfiable())
+    }
+
+    /// Draw a candidate, ignoring lookaheads and anchors
+    fn sample(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        let mut out = String::new();
+        sample(&self.root, &mut out, rng)?;
+        Ok(out)
+    }
+}
+
+fn unsatisfiable() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "the pattern's lookaheads and anchors are (almost) never satisfied",
+    )
+}
+
+/// Recursive-descent parser over the pattern's characters
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Lookaheads are only matched, never sampled, so they may be unbounded
+    lookahead_depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        let matched = self.chars.len() >= self.pos + len
+            && self.chars[self.pos..self.pos + len]
+                .iter()
+                .copied()
+                .eq(s.chars());
+        if matched {
+            self.pos += len;
+        }
+        matched
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.eat('|') {
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alternation(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::Concat(nodes)
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        let position = self.pos + 1;
+        let c = self.peek().ok_or("unexpected end of pattern")?;
+        self.pos += 1;
+
+        Ok(match c {
+            '(' => {
+                let lookahead = if self.eat_str("?=") {
+                    Some(false)
+                } else if self.eat_str("?!") {
+                    Some(true)
+                } else if self.eat_str("?:") {
+                    None
+                } else if self.eat_str("?<=") || self.eat_str("?<!") {
+                    return Err("lookbehinds are not supported".to_string());
+                } else if self.peek() == Some('?') {
+                    return Err(format!(
+                        "unsupported group syntax '(?' at position {}",
+                        position
+                    ));
+                } else {
+                    None
+                };
+
+                if lookahead.is_some() {
+                    self.lookahead_depth += 1;
+                }
+                let node = self.parse_alternation()?;
+                if lookahead.is_some() {
+                    self.lookahead_depth -= 1;
+                }
+                if !self.eat(')') {
+                    return Err(format!("unclosed group opened at position {}", position));
+                }
+
+                match lookahead {
+                    Some(negative) => Node::Lookahead {
+                        negative,
+                        node: Box::new(node),
+                    },
+                    None => node,
+                }
+            }
+            '[' => Node::Set(self.parse_class(position)?),
+            '.' => Node::Set(printable()),
+            '^' => Node::Start,
+            '$' => Node::End,
+            '\\' => Node::Set(self.parse_escape(false)?),
+            '*' | '+' | '?' => {
+                return Err(format!("nothing to repeat at position {}", position));
+            }
+            '{' if self.braces_at(position - 1).is_some() => {
+                return Err(format!("nothing to repeat at position {}", position));
+            }
+            c => Node::Set(vec![c]),
+        })
+    }
+
+    /// The bounds and text of a `{n}`, `{n,}` or `{n,m}` quantifier whose
+    /// `{` is at `start`
+    fn braces_at(&self, start: usize) -> Option<(usize, Option<usize>, String)> {
+        let inner: String = self.chars[start + 1..]
+            .iter()
+            .take_while(|&&c| c != '}')
+            .collect();
+        let closed = self.chars.len() > start + 1 + inner.chars().count();
+        let (min, max) = parse_bounds(&inner).filter(|_| closed)?;
+        Some((min, max, format!("{{{}}}", inner)))
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let position = self.pos + 1;
+        let (min, max, text) = match self.peek() {
+            Some('*') => (0, None, "*".to_string()),
+            Some('+') => (1, None, "+".to_string()),
+            Some('?') => (0, Some(1), "?".to_string()),
+            // A brace that isn't a quantifier is a literal
+            Some('{') => match self.braces_at(self.pos) {
+                Some(quantifier) => quantifier,
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        self.pos += text.chars().count();
+
+        // Lazy and possessive modifiers don't change what can match
+        if !self.eat('?') {
+            self.eat('+');
+        }
+
+        if matches!(atom, Node::Start | Node::End | Node::Lookahead { .. }) {
+            return Err(format!(
+                "cannot repeat an anchor or lookahead at position {}",
+                position
+            ));
+        }
+        if max.is_some_and(|max| min > max) {
+            return Err(format!(
+                "invalid repetition '{}': minimum exceeds maximum",
+                text
+            ));
+        }
+        if max.is_none() && self.lookahead_depth == 0 {
+            return Err(format!(
+                "unbounded repetition '{}' at position {} cannot be generated; use a bounded count such as {{8,64}}",
+                text, position
+            ));
+        }
+
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    /// Parse a bracketed class after its opening `[`
+    fn parse_class(&mut self, position: usize) -> Result<Vec<char>, String> {
+        let negated = self.eat('^');
+        let mut set = Vec::new();
+        let mut first = true;
+
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| format!("unclosed class opened at position {}", position))?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let start = if c == '\\' {
+                let chars = self.parse_escape(true)?;
+                if chars.len() > 1 {
+                    set.extend(chars);
+                    continue;
+                }
+                chars[0]
+            } else {
+                c
+            };
+
+            // A '-' right before ']' is a literal
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let end = match self.peek() {
+                    Some('\\') => {
+                        self.pos += 1;
+                        let chars = self.parse_escape(true)?;
+                        if chars.len() > 1 {
+                            return Err(format!("invalid range in class at position {}", position));
+                        }
+                        chars[0]
+                    }
+                    Some(c) => {
+                        self.pos += 1;
+                        c
+                    }
+                    None => unreachable!(),
+                };
+                if end < start {
+                    return Err(format!("invalid range '{}-{}' in class", start, end));
+                }
+                set.extend(start..=end);
+            } else {
+                set.push(start);
+            }
+        }
+
+        set.sort_unstable();
+        set.dedup();
+        if negated {
+            set = printable()
+                .into_iter()
+                .filter(|c| set.binary_search(c).is_err())
+                .collect();
+        }
+        if set.is_empty() {
+            return Err(format!("class at position {} matches nothing", position));
+        }
+        Ok(set)
+    }
+
+    /// Parse an escape after its backslash, returning the characters it matches
+    fn parse_escape(&mut self, in_class: bool) -> Result<Vec<char>, String> {
+        let c = self.peek().ok_or("pattern ends with a lone '\\'")?;
+        self.pos += 1;
+
+        let digits: Vec<char> = ('0'..='9').collect();
+        let word: Vec<char> = ('0'..='9')
+            .chain('A'..='Z')
+            .chain(std::iter::once('_'))
+            .chain('a'..='z')
+            .collect();
+        let space = vec![' '];
+        let without = |excluded: &[char]| -> Vec<char> {
+            printable()
+                .into_iter()
+                .filter(|c| !excluded.contains(c))
+                .collect()
+        };
+
+        Ok(match c {
+            'd' => digits,
+            'D' => without(&digits),
+            'w' => word,
+            'W' => without(&word),
+            's' => space,
+            'S' => without(&space),
+            'n' => vec!['\n'],
+            't' => vec!['\t'],
+            'r' => vec!['\r'],
+            'x' => {
+                let hex: String = self.chars.iter().skip(self.pos).take(2).collect();
+                let value = u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 2)
+                    .ok_or("'\\x' needs two hex digits")?;
+                self.pos += 2;
+                vec![value as char]
+            }
+            'b' | 'B' if !in_class => return Err("word boundaries are not supported".to_string()),
+            '1'..='9' => return Err("backreferences are not supported".to_string()),
+            c if c.is_ascii_alphanumeric() => return Err(format!("unsupported escape '\\{}'", c)),
+            c => vec![c],
+        })
+    }
+}
+
+/// Parse the inside of `{n}`, `{n,}` or `{n,m}`
+fn parse_bounds(s: &str) -> Option<(usize, Option<usize>)> {
+    let number = |s: &str| -> Option<usize> {
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    };
+    match s.split_once(',') {
+        None => number(s).map(|n| (n, Some(n))),
+        Some((min, "")) => number(min).map(|min| (min, None)),
+        Some((min, max)) => Some((number(min)?, Some(number(max)?))),
+    }
+}
+
+/// Longest string a node can produce, saturating
+fn max_length(node: &Node) -> usize {
+    match node {
+        Node::Set(_) => 1,
+        Node::Concat(nodes) => nodes
+            .iter()
+            .fold(0, |total, n| total.saturating_add(max_length(n))),
+        Node::Alternation(branches) => branches.iter().map(max_length).max().unwrap_or(0),
+        Node::Repeat { node, max, .. } => {
+            max_length(node).saturating_mul(max.unwrap_or(usize::MAX))
+        }
+        Node::Start | Node::End | Node::Lookahead { .. } => 0,
+    }
+}
+
+/// Whether a node has no anchors or lookaheads
+fn is_free(node: &Node) -> bool {
+    match node {
+        Node::Set(_) => true,
+        Node::Concat(nodes) | Node::Alternation(nodes) => nodes.iter().all(is_free),
+        Node::Repeat { node, .. } => is_free(node),
+        Node::Start | Node::End | Node::Lookahead { .. } => false,
+    }
+}
+
+/// Whether a top-level branch only has anchors at its ends
+///
+/// Generated strings always match the whole pattern, so those anchors hold.
+fn branch_is_free(node: &Node) -> bool {
+    match node {
+        Node::Start | Node::End => true,
+        Node::Concat(nodes) => {
+            let mut inner = &nodes[..];
+            if let Some((Node::Start, rest)) = inner.split_first() {
+                inner = rest;
+            }
+            if let Some((Node::End, rest)) = inner.split_last() {
+                inner = rest;
+            }
+            inner.iter().all(is_free)
+        }
+        other => is_free(other),
+    }
+}
+
+/// Number of ways to fill in a node
+fn count(node: &Node) -> BigUint {
+    match node {
+        Node::Set(set) => BigUint::from(set.len() as u64),
+        Node::Concat(nodes) => nodes
+            .iter()
+            .fold(BigUint::one(), |total, n| &total * &count(n)),
+        Node::Alternation(branches) => branches
+            .iter()
+            .fold(BigUint::zero(), |total, n| &total + &count(n)),
+        Node::Repeat { .. } => repeat_weights(node)
+            .iter()
+            .fold(BigUint::zero(), |total, w| &total + w),
+        Node::Start | Node::End | Node::Lookahead { .. } => BigUint::one(),
+    }
+}
+
+/// Number of ways to fill in each repetition count of a bounded repeat
+fn repeat_weights(node: &Node) -> Vec<BigUint> {
+    let Node::Repeat { node, min, max } = node else {
+        unreachable!("not a repetition");
+    };
+    let max = max.expect("sampled repetitions are bounded");
+    let inner = count(node);
+
+    let mut weight = inner.pow(*min as u32);
+    let mut weights = Vec::with_capacity(max - min + 1);
+    for _ in *min..=max {
+        weights.push(weight.clone());
+        weight = &weight * &inner;
+    }
+    weights
+}
+
+/// Append a random expansion of a node
+///
+/// Alternatives and repetition counts are weighted by how many ways they
+/// can be filled in, so every expansion is equally likely.
+fn sample(node: &Node, out: &mut String, rng: &mut dyn RandomSource) -> io::Result<()> {
+    match node {
+        Node::Set(set) => out.push(set[rng.random_range(set.len())?]),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                sample(node, out, rng)?;
+            }
+        }
+        Node::Alternation(branches) => {
+            let weights: Vec<BigUint> = branches.iter().map(count).collect();
+            sample(
+                &branches[BigUint::random_weighted(&weights, rng)?],
+                out,
+                rng,
+            )?;
+        }
+        Node::Repeat {
+            node: inner, min, ..
+        } => {
+            let times = min + BigUint::random_weighted(&repeat_weights(node), rng)?;
+            for _ in 0..times {
+                sample(inner, out, rng)?;
+            }
+        }
+        Node::Start | Node::End | Node::Lookahead { .. } => {}
+    }
+    Ok(())
+}
+
+/// Backtracking matcher: whether `node` matches at `pos` with `next`
+/// accepting where it ends
+fn matches(node: &Node, input: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+    match node {
+        Node::Set(set) => {
+            pos < input.len() && set.binary_search(&input[pos]).is_ok() && next(pos + 1)
+        }
+        Node::Concat(nodes) => matches_sequence(nodes, input, pos, next),
+        Node::Alternation(branches) => branches.iter().any(|b| matches(b, input, pos, next)),
+        Node::Repeat { node, min, max } => matches_repeat(node, *min, *max, 0, input, pos, next),
+        Node::Start => pos == 0 && next(pos),
+        Node::End => pos == input.len() && next(pos),
+        Node::Lookahead { negative, node } => {
+            matches(node, input, pos, &mut |_| true) != *negative && next(pos)
+        }
+    }
+}
+
+fn matches_sequence(
+    nodes: &[Node],
+    input: &[char],
+    pos: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    match nodes.split_first() {
+        None => next(pos),
+        Some((first, rest)) => matches(first, input, pos, &mut |end| {
+            matches_sequence(rest, input, end, next)
+        }),
+    }
+}
+
+/// Greedy repetition, having matched `done` times so far
+fn matches_repeat(
+    node: &Node,
+    min: usize,
+    max: Option<usize>,
+    done: usize,
+    input: &[char],
+    pos: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    if max.is_none_or(|max| done < max) {
+        // Once the minimum is met, an empty iteration can't make progress
+        let more = matches(node, input, pos, &mut |end| {
+            (end != pos || done < min) && matches_repeat(node, min, max, done + 1, input, end, next)
+        });
+        if more {
+            return true;
+        }
+    }
+    done >= min && next(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_with_lookaheads() {
+        let regex =
+            RegexGenerator::new(r"^(?=.*[a-z])(?=.*[A-Z])(?=.*\d)(?!.*\s)[A-Za-z\d!@#$%]{8,16}$")
+                .unwrap();
+        assert!(regex.is_constrained());
+
+        for _ in 0..20 {
+            let password = regex.generate().unwrap();
+            assert!((8..=16).contains(&password.len()));
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(regex.is_match(&password));
+        }
+
+        let entropy = regex.estimate_entropy().unwrap();
+        assert!(entropy < regex.keyspace().log2());
+        assert!(entropy > regex.keyspace().log2() - 2.0);
+    }
+
+    #[test]
+    fn test_keyspace() {
+        let regex = RegexGenerator::new(r"[a-c]{2}-\d").unwrap();
+        assert_eq!(regex.keyspace().to_string(), "90");
+        assert!(!regex.is_constrained());
+        assert!((regex.estimate_entropy().unwrap() - 90f64.log2()).abs() < 1e-9);
+
+        // 10 + 100 + 1000
+        let regex = RegexGenerator::new(r"^\d{1,3}$").unwrap();
+        assert_eq!(regex.keyspace().to_string(), "1110");
+        assert!(!regex.is_constrained());
+
+        let regex = RegexGenerator::new("(cat|dog|bird)s?").unwrap();
+        assert_eq!(regex.keyspace().to_string(), "6");
+    }
+
+    #[test]
+    fn test_alternation_and_classes() {
+        let regex = RegexGenerator::new(r"(?:ab|[^\x20-\x7d])x{2}").unwrap();
+        for _ in 0..20 {
+            let s = regex.generate().unwrap();
+            assert!(s == "abxx" || s == "~xx", "{}", s);
+        }
+
+        let regex = RegexGenerator::new(r"[\w.-]{4}").unwrap();
+        let s = regex.generate().unwrap();
+        assert!(
+            s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c))
+        );
+        assert_eq!(regex.keyspace().to_string(), 65u64.pow(4).to_string());
+    }
+
+    #[test]
+    fn test_matcher() {
+        let regex = RegexGenerator::new(r"^(?=.*\d)(?!.*\d\d)[a-z0-9]{2,4}$").unwrap();
+        assert!(regex.is_match("ab1"));
+        assert!(!regex.is_match("abc"));
+        assert!(!regex.is_match("a12"));
+        assert!(!regex.is_match("a1b2c"));
+
+        let regex = RegexGenerator::new("(a?){3}b").unwrap();
+        assert!(regex.is_match("b"));
+        assert!(regex.is_match("aab"));
+        assert!(!regex.is_match("aaaab"));
+    }
+
+    #[test]
+    fn test_literal_braces() {
+        let regex = RegexGenerator::new("a{x}").unwrap();
+        assert_eq!(regex.generate().unwrap(), "a{x}");
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        for pattern in [
+            "a*", "a+", "a{3,}", ".*x", "(a", "a)", "[a-", "[z-a]", "?", "a{3,2}", r"\1",
+            r"\bword", "(?<=a)b", "(?i)a", "^*", r"a\", "[^ -~]",
+        ] {
+            assert!(RegexGenerator::new(pattern).is_err(), "{}", pattern);
+        }
+
+        let error = RegexGenerator::new(".+").unwrap_err();
+        assert!(error.contains("unbounded"), "{}", error);
+
+        assert!(RegexGenerator::new(".{2000}").is_err());
+        assert!(RegexGenerator::new("(.{100}){100}").is_err());
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        let regex = RegexGenerator::new(r"(?=.*\d)[a-z]{4}").unwrap();
+        assert!(regex.generate().is_err());
+        assert!(regex.estimate_entropy().is_err());
+
+        let regex = RegexGenerator::new("a^b").unwrap();
+        assert!(regex.is_constrained());
+        assert!(regex.generate().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::health::{APT_CUTOFF, HealthTests, RCT_CUTOFF};
+use crate::random::{self, ChaChaRng, RandomSource, SecureRandom};
+use crate::sha256::Sha256;
+
+/// Bytes the start-up test reads from the OS; SP 800-90B asks for at least 1024
+const STARTUP_SAMPLES: usize = 4096;
+
+/// Bytes of DRBG output run through the health tests
+const DRBG_SAMPLES: usize = 1 << 16;
+
+/// The outcome of one self-test
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<(), String>,
+}
+
+/// A self-test: its name and the function that runs it
+type Test = (&'static str, fn() -> Result<(), String>);
+
+const TESTS: [Test; 5] = [
+    ("SHA-256 known answers (FIPS 180-4)", sha256),
+    ("ChaCha20 known answers (RFC 8439)", chacha20),
+    ("Health tests catch a failing source", health_tests),
+    ("Start-up health test of the OS source", os_startup),
+    ("Health tests of the ChaCha20 DRBG output", drbg),
+];
+
+/// Run the known-answer tests for the in-crate crypto and the health tests
+/// of the random sources
+pub fn run() -> Vec<Check> {
+    TESTS
+        .into_iter()
+        .map(|(name, test)| Check {
+            name,
+            result: test(),
+        })
+        .collect()
+}
+
+/// Compare bytes with the expected hex string
+fn expect(what: &str, bytes: &[u8], expected: &str) -> Result<(), String> {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    if hex == expected {
+        Ok(())
+    } else {
+        Err(format!("{}: expected {}, got {}", what, expected, hex))
+    }
+}
+
+fn sha256() -> Result<(), String> {
+    expect(
+        "empty input",
+        &Sha256::digest(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    )?;
+    expect(
+        "\"abc\"",
+        &Sha256::digest(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    )
+}
+
+fn chacha20() -> Result<(), String> {
+    // Section 2.3.2 and appendix A.1, test vector #1
+    let key: [u8; 32] = std::array::from_fn(|i| i as u8);
+    let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+    expect(
+        "block function",
+        &random::chacha20_block(&key, 1, &nonce),
+        "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e\
+         d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e",
+    )?;
+    expect(
+        "all-zero key",
+        &random::chacha20_block(&[0; 32], 0, &[0; 12]),
+        "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7\
+         da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586",
+    )
+}
+
+fn health_tests() -> Result<(), String> {
+    if HealthTests::new().check(&[0x5a; RCT_CUTOFF]).is_ok() {
+        return Err("the repetition count test missed a stuck byte".to_string());
+    }
+
+    let biased: Vec<u8> = (0..APT_CUTOFF as u8).flat_map(|i| [0, i + 1]).collect();
+    if HealthTests::new().check(&biased).is_ok() {
+        return Err("the adaptive proportion test missed a biased source".to_string());
+    }
+    Ok(())
+}
+
+fn os_startup() -> Result<(), String> {
+    let mut bytes = [0u8; STARTUP_SAMPLES];
+    SecureRandom::fill_os_bytes(&mut bytes).map_err(|e| e.to_string())?;
+    HealthTests::new().check(&bytes).map_err(|e| e.to_string())
+}
+
+fn drbg() -> Result<(), String> {
+    let mut rng = ChaChaRng::new().map_err(|e| e.to_string())?;
+    let mut bytes = vec![0u8; DRBG_SAMPLES];
+    rng.fill_bytes(&mut bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_pass() {
+        let checks = run();
+        assert_eq!(checks.len(), TESTS.len());
+        for check in checks {
+            assert_eq!(check.result, Ok(()), "{}", check.name);
+        }
+    }
+
+    #[test]
+    fn test_expect() {
+        assert!(expect("bytes", &[0xab, 0x01], "ab01").is_ok());
+        let e = expect("bytes", &[0xab], "ac").unwrap_err();
+        assert_eq!(e, "bytes: expected ac, got ab");
+    }
+}
@@ -0,0 +1,106 @@
+/// Upper-tail standard normal quantile for a significance of 10^-6
+const Z: f64 = 4.753;
+
+/// Pearson's chi-square statistic of observed counts against expected counts
+pub fn chi_square(observed: &[u64], expected: &[f64]) -> f64 {
+    observed
+        .iter()
+        .zip(expected)
+        .map(|(&o, &e)| (o as f64 - e).powi(2) / e)
+        .sum()
+}
+
+/// Critical chi-square value for `df` degrees of freedom at a significance
+/// of 10^-6
+///
+/// Uses the Wilson-Hilferty approximation, which errs on the lenient side
+/// for very few degrees of freedom.
+pub fn critical_value(df: usize) -> f64 {
+    let df = df as f64;
+    let h = 2.0 / (9.0 * df);
+    df * (1.0 - h + Z * h.sqrt()).powi(3)
+}
+
+/// Whether counts fit the expected probabilities
+pub fn fits(observed: &[u64], probabilities: &[f64]) -> bool {
+    let total: u64 = observed.iter().sum();
+    let expected: Vec<f64> = probabilities.iter().map(|p| p * total as f64).collect();
+    chi_square(observed, &expected) < critical_value(observed.len() - 1)
+}
+
+/// Assert that counts fit the expected probabilities
+pub fn assert_fits(what: &str, observed: &[u64], probabilities: &[f64]) {
+    let total: u64 = observed.iter().sum();
+    let expected: Vec<f64> = probabilities.iter().map(|p| p * total as f64).collect();
+    let statistic = chi_square(observed, &expected);
+    let critical = critical_value(observed.len() - 1);
+    assert!(
+        statistic < critical,
+        "{}: chi-square {:.1} exceeds {:.1} for {:?}",
+        what,
+        statistic,
+        critical,
+        observed
+    );
+}
+
+/// Assert that counts are uniform
+pub fn assert_uniform(what: &str, observed: &[u64]) {
+    let probabilities = vec![1.0 / observed.len() as f64; observed.len()];
+    assert_fits(what, observed, &probabilities);
+}
+
+/// Position of a permutation of 0..4 in lexicographic order
+pub fn permutation_index(items: &[usize; 4]) -> usize {
+    let mut index = 0;
+    for i in 0..4 {
+        let smaller_after = items[i + 1..].iter().filter(|&&x| x < items[i]).count();
+        index = index * (4 - i) + smaller_after;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{RandomSource, SeededRandom};
+
+    #[test]
+    fn test_critical_value() {
+        // Exact values: 23.93 (1), 46.86 (10), 182.13 (100)
+        assert!((critical_value(10) - 46.86).abs() < 1.5);
+        assert!((critical_value(100) - 182.13).abs() < 0.5);
+        assert!(critical_value(1) > 23.93);
+    }
+
+    #[test]
+    fn test_catches_modulo_bias() {
+        // A byte taken modulo 100 favours 0-55
+        let mut rng = SeededRandom::new(1);
+        let mut bytes = vec![0u8; 100_000];
+        rng.fill_bytes(&mut bytes).unwrap();
+
+        let mut counts = vec![0u64; 100];
+        for &b in &bytes {
+            counts[b as usize % 100] += 1;
+        }
+        assert!(!fits(&counts, &[0.01; 100]));
+    }
+
+    #[test]
+    fn test_catches_broken_shuffle() {
+        // Always swapping with a strictly earlier element never leaves an
+        // item in place, so many permutations never appear
+        let mut rng = SeededRandom::new(2);
+        let mut counts = vec![0u64; 24];
+        for _ in 0..24_000 {
+            let mut items = [0, 1, 2, 3];
+            for i in (1..4).rev() {
+                let j = rng.random_range(i).unwrap();
+                items.swap(i, j);
+            }
+            counts[permutation_index(&items)] += 1;
+        }
+        assert!(!fits(&counts, &[1.0 / 24.0; 24]));
+    }
+}
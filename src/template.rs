@@ -0,0 +1,317 @@
+use crate::generator::{self, LOWERCASE, NUMERIC, UPPERCASE};
+use crate::mask::{Mask, MaskOptions};
+use crate::passphrase::{self, Capitalization};
+use crate::random::{RandomSource, SecureRandom};
+use crate::wordlist::Wordlist;
+use std::io;
+
+/// Hex digits; never filtered, since hex output must stay hex
+const HEX: &str = "0123456789abcdef";
+
+/// What a template can draw from
+#[derive(Debug, Clone)]
+pub struct TemplateOptions {
+    /// Symbols, ambiguity filtering and custom charsets, as for masks
+    pub mask: MaskOptions,
+    /// Wordlist for `{word}`
+    pub wordlist: Wordlist,
+}
+
+/// One piece of a template
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    /// `count` characters drawn from `set`
+    Chars {
+        set: Vec<char>,
+        count: usize,
+    },
+    Word(Capitalization),
+    Mask(Mask),
+}
+
+/// A password template such as `{word}-{word}-{digits:4}{symbol}`
+///
+/// Placeholders are `{name}` or `{name:param}`:
+///
+/// - `{word}`, `{word:first}`, `{word:upper}`: a word from the wordlist
+/// - `{digits:N}`, `{hex:N}`, `{symbol:N}`, `{lower:N}`, `{upper:N}`,
+///   `{letters:N}`, `{alnum:N}`, `{chars:N}`: N characters of a class (default 1)
+/// - `{mask:MASK}`: a hashcat-style mask
+///
+/// Everything else is literal text; `{{` and `}}` produce braces.
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+    wordlist: Wordlist,
+}
+
+impl Template {
+    /// Parse a template
+    pub fn parse(template: &str, options: TemplateOptions) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| format!("unclosed placeholder '{{{}'", rest))?;
+                    let placeholder = &rest[..end];
+                    chars = rest[end + 1..].chars();
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Self::placeholder(placeholder, &options)?);
+                }
+                '}' => return Err("unmatched '}'; use '}}' for a literal brace".to_string()),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        if segments.is_empty() {
+            return Err("template is empty".to_string());
+        }
+        let uses_words = segments.iter().any(|s| matches!(s, Segment::Word(_)));
+        if uses_words && options.wordlist.is_empty() {
+            return Err("template uses {word} but the wordlist is empty".to_string());
+        }
+
+        Ok(Self {
+            segments,
+            wordlist: options.wordlist,
+        })
+    }
+
+    /// Resolve one `name` or `name:param` placeholder
+    fn placeholder(placeholder: &str, options: &TemplateOptions) -> Result<Segment, String> {
+        let (name, param) = match placeholder.split_once(':') {
+            Some((name, param)) => (name.trim(), Some(param)),
+            None => (placeholder.trim(), None),
+        };
+
+        let filter = |set: &str| generator::filter_ambiguous(set, options.mask.exclude_ambiguous);
+        let set = match name {
+            "word" => {
+                let capitalization = match param {
+                    None => Capitalization::Lower,
+                    Some(param) => match param.trim().parse()? {
+                        Capitalization::Random => {
+                            return Err("{word} does not support random capitalization".to_string());
+                        }
+                        other => other,
+                    },
+                };
+                return Ok(Segment::Word(capitalization));
+            }
+            "mask" => {
+                let mask = param.ok_or("{mask} needs a mask, e.g. {mask:?u?d?d}")?;
+                return Mask::parse(mask, &options.mask).map(Segment::Mask);
+            }
+            "digits" => filter(NUMERIC),
+            "hex" => HEX.chars().collect(),
+            "symbol" => generator::unique_chars(filter(&options.mask.symbols)),
+            "lower" => filter(LOWERCASE),
+            "upper" => filter(UPPERCASE),
+            "letters" => filter(&format!("{}{}", LOWERCASE, UPPERCASE)),
+            "alnum" => filter(&format!("{}{}{}", LOWERCASE, UPPERCASE, NUMERIC)),
+            "chars" => generator::unique_chars(filter(&format!(
+                "{}{}{}{}",
+                LOWERCASE, UPPERCASE, NUMERIC, options.mask.symbols
+            ))),
+            _ => return Err(format!("unknown placeholder '{{{}}}'", placeholder)),
+        };
+
+        let count = match param {
+            None => 1,
+            Some(param) => match param.trim().parse() {
+                // Passwords are capped at MAX_LENGTH characters
+                Ok(count) if count > 0 && count <= generator::MAX_LENGTH => count,
+                _ => {
+                    return Err(format!(
+                        "invalid count '{}' in '{{{}}}'",
+                        param, placeholder
+                    ));
+                }
+            },
+        };
+        if set.is_empty() {
+            return Err(format!(
+                "'{{{}}}' has no characters to choose from",
+                placeholder
+            ));
+        }
+
+        Ok(Segment::Chars { set, count })
+    }
+
+    /// Total entropy across all segments, in bits
+    ///
+    /// Segments are drawn independently, so their entropies add up. This
+    /// is exact as long as the output can be split back into segments;
+    /// see `warnings`.
+    pub fn entropy(&self) -> f64 {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(_) => 0.0,
+                Segment::Chars { set, count } => *count as f64 * (set.len() as f64).log2(),
+                Segment::Word(_) => self.wordlist.entropy_per_word(),
+                Segment::Mask(mask) => mask.entropy(),
+            })
+            .sum()
+    }
+
+    /// Warnings about segments whose boundaries can be ambiguous
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        // A word of variable length runs into whatever random text follows it
+        let follows_word = self.segments.windows(2).any(|pair| {
+            matches!(pair[0], Segment::Word(_)) && !matches!(pair[1], Segment::Literal(_))
+        });
+        if follows_word {
+            warnings.push(
+                "{word} is directly followed by another placeholder; the entropy is an upper bound"
+                    .to_string(),
+            );
+        }
+
+        warnings
+    }
+
+    /// Generate a password from the template
+    pub fn generate(&self) -> io::Result<String> {
+        self.generate_with(&mut SecureRandom)
+    }
+
+    /// Generate a password from the template using the given random source
+    pub fn generate_with(&self, rng: &mut dyn RandomSource) -> io::Result<String> {
+        let mut password = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => password.push_str(text),
+                Segment::Chars { set, count } => {
+                    for _ in 0..*count {
+                        password.push(set[rng.random_range(set.len())?]);
+                    }
+                }
+                Segment::Word(capitalization) => {
+                    let index = rng.random_range(self.wordlist.len())?;
+                    let word = self.wordlist.get(index).unwrap_or_default();
+                    password.push_str(&passphrase::capitalize(word, *capitalization));
+                }
+                Segment::Mask(mask) => password.push_str(&mask.generate_with(rng)?),
+            }
+        }
+        Ok(password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> TemplateOptions {
+        TemplateOptions {
+            mask: MaskOptions {
+                symbols: "!@#$".to_string(),
+                exclude_ambiguous: false,
+                charsets: Default::default(),
+            },
+            wordlist: Wordlist::eff_short(),
+        }
+    }
+
+    #[test]
+    fn test_words_digits_symbol() {
+        let template = Template::parse("{word}-{word}-{digits:4}{symbol}", options()).unwrap();
+        let password = template.generate().unwrap();
+
+        let parts: Vec<&str> = password.split('-').collect();
+        assert_eq!(parts.len(), 3);
+        assert!(parts[0].chars().all(|c| c.is_ascii_lowercase()));
+        assert!(parts[2][..4].chars().all(|c| c.is_ascii_digit()));
+        assert!("!@#$".contains(&parts[2][4..]));
+
+        let expected = 2.0 * 1296f64.log2() + 4.0 * 10f64.log2() + 2.0;
+        assert!((template.entropy() - expected).abs() < 1e-9);
+        assert!(template.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_hex_and_literals() {
+        let template = Template::parse("svc-{hex:32}", options()).unwrap();
+        let password = template.generate().unwrap();
+        assert!(password.starts_with("svc-"));
+        assert_eq!(password.len(), 36);
+        assert!(password[4..].chars().all(|c| c.is_ascii_hexdigit()));
+        assert!((template.entropy() - 128.0).abs() < 1e-9);
+
+        let template = Template::parse("{{x}}", options()).unwrap();
+        assert_eq!(template.generate().unwrap(), "{x}");
+        assert_eq!(template.entropy(), 0.0);
+    }
+
+    #[test]
+    fn test_word_capitalization_and_mask() {
+        let template = Template::parse("{word:upper}.{mask:?u?d}", options()).unwrap();
+        let password = template.generate().unwrap();
+        let (word, mask) = password.split_once('.').unwrap();
+        assert!(word.chars().all(|c| c.is_ascii_uppercase()));
+        assert!(mask.chars().next().unwrap().is_ascii_uppercase());
+
+        let expected = 1296f64.log2() + 260f64.log2();
+        assert!((template.entropy() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_exclude_ambiguous() {
+        let mut opts = options();
+        opts.mask.exclude_ambiguous = true;
+        let template = Template::parse("{alnum:40}", opts).unwrap();
+        let password = template.generate().unwrap();
+        assert!(!password.contains(['0', 'O', '1', 'l', 'I']));
+        assert!((template.entropy() - 40.0 * 57f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_adjacent_word_warning() {
+        let template = Template::parse("{word}{digits:2}", options()).unwrap();
+        assert_eq!(template.warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(Template::parse("", options()).is_err());
+        assert!(Template::parse("{word", options()).is_err());
+        assert!(Template::parse("a}b", options()).is_err());
+        assert!(Template::parse("{nope}", options()).is_err());
+        assert!(Template::parse("{digits:0}", options()).is_err());
+        assert!(Template::parse("{digits:x}", options()).is_err());
+        assert!(Template::parse("{hex:99999999999}", options()).is_err());
+        assert!(Template::parse("{digits:4097}", options()).is_err());
+        assert!(Template::parse("{digits:4096}", options()).is_ok());
+        assert!(Template::parse("{word:random}", options()).is_err());
+        assert!(Template::parse("{mask}", options()).is_err());
+
+        let mut opts = options();
+        opts.mask.symbols = String::new();
+        assert!(Template::parse("{symbol}", opts).is_err());
+    }
+}
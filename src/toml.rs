@@ -0,0 +1,654 @@
+use crate::config::Diagnostic;
+use std::fmt::{self, Write};
+
+/// A TOML value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// The value's type, for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Float(_) => "a float",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write_string(f, s),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(x) if x.is_nan() => f.write_str("nan"),
+            Value::Float(x) if x.is_infinite() => {
+                f.write_str(if *x > 0.0 { "inf" } else { "-inf" })
+            }
+            Value::Float(x) => {
+                // A float must keep its point, or it reads back as an integer
+                let s = x.to_string();
+                if s.contains(['.', 'e', 'E']) {
+                    f.write_str(&s)
+                } else {
+                    write!(f, "{}.0", s)
+                }
+            }
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+        }
+    }
+}
+
+/// A basic string with everything that needs it escaped
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            c if c.is_control() => write!(f, "\\u{:04X}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// A key as written in a document: bare if it can be, quoted otherwise
+pub fn key(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(is_bare) {
+        name.to_string()
+    } else {
+        Value::String(name.to_string()).to_string()
+    }
+}
+
+fn is_bare(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// One `key = value` pair of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Dotted name of the table holding the key, empty at the top level
+    pub table: String,
+    pub key: String,
+    pub value: Value,
+    /// Line of the key, counting from 1
+    pub line: usize,
+    /// Line the value ends on; later than `line` for multi-line arrays
+    pub end_line: usize,
+    /// Comment after the value, including the `#`
+    pub comment: Option<String>,
+}
+
+/// A `[table]` header
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub name: String,
+    pub line: usize,
+}
+
+/// A parsed document, in file order
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    pub tables: Vec<Table>,
+    pub entries: Vec<Entry>,
+}
+
+/// Parse the subset of TOML used for profiles
+///
+/// Supported are tables, single-line strings, integers, floats, booleans
+/// and arrays, which may span lines. Lines that fail to parse are
+/// reported and skipped, so one typo doesn't hide the rest.
+pub fn parse(content: &str) -> (Document, Vec<Diagnostic>) {
+    let mut parser = Parser {
+        chars: content.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut document = Document::default();
+    let mut diagnostics = Vec::new();
+    let mut table = String::new();
+
+    loop {
+        parser.skip_blank();
+        let Some(c) = parser.peek() else {
+            break;
+        };
+        let line = parser.line;
+
+        let result = if c == '[' {
+            parser.table_header().map(|name| {
+                let duplicate = document.tables.iter().find(|t| t.name == name);
+                let problem = duplicate.map(|first| {
+                    format!("duplicate table [{}] (first on line {})", name, first.line)
+                });
+                // Keys below a repeated header still belong to it
+                table = name.clone();
+                document.tables.push(Table { name, line });
+                problem
+            })
+        } else {
+            parser.entry(&table).map(|entry| {
+                let duplicate = document
+                    .entries
+                    .iter()
+                    .find(|e| e.table == entry.table && e.key == entry.key);
+                match duplicate {
+                    Some(first) => Some(format!(
+                        "duplicate key '{}' (first set on line {})",
+                        entry.key, first.line
+                    )),
+                    None => {
+                        document.entries.push(entry);
+                        None
+                    }
+                }
+            })
+        };
+
+        match result {
+            Ok(None) => {}
+            Ok(Some(message)) => diagnostics.push(Diagnostic { line, message }),
+            Err(message) => {
+                diagnostics.push(Diagnostic { line, message });
+                parser.skip_line();
+            }
+        }
+    }
+
+    (document, diagnostics)
+}
+
+/// Recursive-descent parser over the document's characters
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Current line, counting from 1
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\r')) {
+            self.bump();
+        }
+    }
+
+    /// Whitespace, line breaks and comments, as allowed between lines and
+    /// between array items
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some('\n') => {
+                    self.bump();
+                }
+                Some('#') => {
+                    self.comment();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n') {
+            self.bump();
+        }
+    }
+
+    fn comment(&mut self) -> Option<String> {
+        if self.peek() != Some('#') {
+            return None;
+        }
+        let start = self.pos;
+        self.skip_line();
+        let comment: String = self.chars[start..self.pos].iter().collect();
+        Some(comment.trim_end().to_string())
+    }
+
+    /// An optional comment, then the end of the line
+    fn end_of_line(&mut self) -> Result<Option<String>, String> {
+        self.skip_spaces();
+        let comment = self.comment();
+        match self.bump() {
+            None | Some('\n') => Ok(comment),
+            Some(c) => Err(format!("expected the end of the line, found '{}'", c)),
+        }
+    }
+
+    fn table_header(&mut self) -> Result<String, String> {
+        self.eat('[');
+        if self.peek() == Some('[') {
+            return Err("arrays of tables are not supported".to_string());
+        }
+
+        let mut parts = Vec::new();
+        loop {
+            self.skip_spaces();
+            parts.push(self.key()?);
+            self.skip_spaces();
+            if !self.eat('.') {
+                break;
+            }
+        }
+        if !self.eat(']') {
+            return Err(format!("expected ']' after [{}", parts.join(".")));
+        }
+        self.end_of_line()?;
+        Ok(parts.join("."))
+    }
+
+    fn entry(&mut self, table: &str) -> Result<Entry, String> {
+        let line = self.line;
+        let key = self.key()?;
+        self.skip_spaces();
+        if self.peek() == Some('.') {
+            return Err(format!(
+                "dotted keys are not supported; put '{}' under a [table]",
+                key
+            ));
+        }
+        if !self.eat('=') {
+            return Err(format!("expected '=' after '{}'", key));
+        }
+        self.skip_spaces();
+        let value = self.value()?;
+        let end_line = self.line;
+        let comment = self.end_of_line()?;
+
+        Ok(Entry {
+            table: table.to_string(),
+            key,
+            value,
+            line,
+            end_line,
+            comment,
+        })
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            Some(c) if is_bare(c) => {
+                let mut key = String::new();
+                while let Some(c) = self.peek().filter(|&c| is_bare(c)) {
+                    key.push(c);
+                    self.bump();
+                }
+                Ok(key)
+            }
+            Some(c) if c != '\n' => Err(format!("expected a key, found '{}'", c)),
+            _ => Err("expected a key".to_string()),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => {
+                Err("multi-line strings are not supported".to_string())
+            }
+            Some('\'') if self.starts_with("'''") => {
+                Err("multi-line strings are not supported".to_string())
+            }
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => Err("inline tables are not supported".to_string()),
+            None | Some('\n' | '#') => Err("missing value".to_string()),
+            Some(_) => {
+                let mut token = String::new();
+                while let Some(c) = self
+                    .peek()
+                    .filter(|c| !matches!(c, ' ' | '\t' | '\r' | '\n' | ',' | ']' | '#'))
+                {
+                    token.push(c);
+                    self.bump();
+                }
+                scalar(&token)
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.eat('[');
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat(']') {
+                break;
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            if self.eat(']') {
+                break;
+            }
+            if !self.eat(',') {
+                return Err("expected ',' or ']' in array".to_string());
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.eat('"');
+        let mut s = String::new();
+        loop {
+            match self.string_char()? {
+                '"' => return Ok(s),
+                '\\' => s.push(self.escape()?),
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// The next character of a string, which must not end the line
+    fn string_char(&mut self) -> Result<char, String> {
+        match self.peek() {
+            None | Some('\n') => Err("unterminated string".to_string()),
+            _ => Ok(self.bump().expect("peeked")),
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let hex_digits = match self.string_char()? {
+            'b' => return Ok('\u{8}'),
+            't' => return Ok('\t'),
+            'n' => return Ok('\n'),
+            'f' => return Ok('\u{c}'),
+            'r' => return Ok('\r'),
+            '"' => return Ok('"'),
+            '\\' => return Ok('\\'),
+            'u' => 4,
+            'U' => 8,
+            c => return Err(format!("invalid escape '\\{}'", c)),
+        };
+
+        let mut code = String::new();
+        for _ in 0..hex_digits {
+            match self.peek() {
+                Some(c) if c.is_ascii_hexdigit() => {
+                    code.push(c);
+                    self.bump();
+                }
+                _ => return Err(format!("expected {} hex digits after '\\u'", hex_digits)),
+            }
+        }
+        u32::from_str_radix(&code, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("'\\u{}' is not a valid character", code))
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.eat('\'');
+        let mut s = String::new();
+        loop {
+            match self.string_char()? {
+                '\'' => return Ok(s),
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+/// A bare value: a boolean, an integer or a float
+fn scalar(token: &str) -> Result<Value, String> {
+    match token {
+        "true" => return Ok(Value::Boolean(true)),
+        "false" => return Ok(Value::Boolean(false)),
+        "inf" | "+inf" => return Ok(Value::Float(f64::INFINITY)),
+        "-inf" => return Ok(Value::Float(f64::NEG_INFINITY)),
+        "nan" | "+nan" | "-nan" => return Ok(Value::Float(f64::NAN)),
+        _ => {}
+    }
+
+    let unsigned = token.strip_prefix(['+', '-']).unwrap_or(token);
+    let cleaned = token.replace('_', "");
+    if is_decimal(unsigned) {
+        return cleaned
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("integer '{}' is out of range", token));
+    }
+
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let is_float = (fraction.is_some() || exponent.is_some())
+        && is_decimal(whole)
+        && fraction.is_none_or(is_digits)
+        && exponent.is_none_or(|e| is_digits(e.strip_prefix(['+', '-']).unwrap_or(e)));
+    if is_float && let Ok(x) = cleaned.parse() {
+        return Ok(Value::Float(x));
+    }
+
+    if token.starts_with(char::is_alphabetic) {
+        Err(format!(
+            "invalid value '{}' (strings must be quoted)",
+            token
+        ))
+    } else {
+        Err(format!("invalid value '{}'", token))
+    }
+}
+
+/// Digits with single underscores between them
+fn is_digits(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars().all(|c| c.is_ascii_digit() || c == '_')
+        && !s.starts_with('_')
+        && !s.ends_with('_')
+        && !s.contains("__")
+}
+
+/// Digits without a leading zero, unless the number is zero
+fn is_decimal(s: &str) -> bool {
+    is_digits(s) && (s == "0" || !s.starts_with('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(content: &str) -> Vec<Value> {
+        let (document, diagnostics) = parse(content);
+        assert_eq!(diagnostics, [], "{}", content);
+        document.entries.into_iter().map(|e| e.value).collect()
+    }
+
+    fn error(content: &str) -> String {
+        let (_, diagnostics) = parse(content);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", content, diagnostics);
+        diagnostics[0].message.clone()
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(
+            values("a = 1\nb = -1_000\nc = 2.5\nd = 1e3\ne = true\nf = \"x\"\ng = 'y\\z'\nh = 0"),
+            [
+                Value::Integer(1),
+                Value::Integer(-1000),
+                Value::Float(2.5),
+                Value::Float(1000.0),
+                Value::Boolean(true),
+                Value::String("x".to_string()),
+                Value::String("y\\z".to_string()),
+                Value::Integer(0),
+            ]
+        );
+        assert_eq!(values("a = inf"), [Value::Float(f64::INFINITY)]);
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(
+            values(r#"a = "tab\there \"q\" \\ é \U0001F600""#),
+            [Value::String("tab\there \"q\" \\ é 😀".to_string())]
+        );
+        assert_eq!(error(r#"a = "\x""#), "invalid escape '\\x'");
+        assert_eq!(
+            error(r#"a = "\uD800""#),
+            "'\\uD800' is not a valid character"
+        );
+    }
+
+    #[test]
+    fn test_tables_and_comments() {
+        let content = "# top\nextends = \"base\"\n\n[length]  # section\nmin = 12 # at least\n\n[a.\"b c\"]\nx = 1\n";
+        let (document, diagnostics) = parse(content);
+        assert_eq!(diagnostics, []);
+        assert_eq!(
+            document.tables,
+            [
+                Table {
+                    name: "length".to_string(),
+                    line: 4
+                },
+                Table {
+                    name: "a.b c".to_string(),
+                    line: 7
+                },
+            ]
+        );
+        let entries = &document.entries;
+        assert_eq!((entries[0].table.as_str(), entries[0].line), ("", 2));
+        assert_eq!(entries[1].table, "length");
+        assert_eq!(entries[1].comment.as_deref(), Some("# at least"));
+        assert_eq!((entries[2].table.as_str(), entries[2].line), ("a.b c", 8));
+    }
+
+    #[test]
+    fn test_multi_line_arrays() {
+        let content = "a = [\n  \"x\",  # first\n  'y',\n]\nb = []\nc = [1, [2, 3]]\n";
+        let (document, diagnostics) = parse(content);
+        assert_eq!(diagnostics, []);
+        let a = &document.entries[0];
+        assert_eq!((a.line, a.end_line), (1, 4));
+        assert_eq!(
+            a.value,
+            Value::Array(vec![
+                Value::String("x".to_string()),
+                Value::String("y".to_string())
+            ])
+        );
+        assert_eq!(document.entries[1].line, 5);
+        assert_eq!(document.entries[2].value.to_string(), "[1, [2, 3]]");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            error("a = two"),
+            "invalid value 'two' (strings must be quoted)"
+        );
+        assert_eq!(error("a = 01"), "invalid value '01'");
+        assert_eq!(error("a = 1."), "invalid value '1.'");
+        assert_eq!(error("a = 1__0"), "invalid value '1__0'");
+        assert_eq!(error("a = \"open"), "unterminated string");
+        assert_eq!(error("a = 1 2"), "expected the end of the line, found '2'");
+        assert_eq!(error("a ="), "missing value");
+        assert_eq!(error("a 1"), "expected '=' after 'a'");
+        assert_eq!(
+            error("a.b = 1"),
+            "dotted keys are not supported; put 'a' under a [table]"
+        );
+        assert_eq!(error("[[a]]"), "arrays of tables are not supported");
+        assert_eq!(error("a = {}"), "inline tables are not supported");
+        assert_eq!(
+            error("a = 99999999999999999999"),
+            "integer '99999999999999999999' is out of range"
+        );
+        assert_eq!(
+            error("a = 1\na = 2"),
+            "duplicate key 'a' (first set on line 1)"
+        );
+        assert_eq!(error("[t]\n[t]"), "duplicate table [t] (first on line 1)");
+
+        // A bad line doesn't hide the ones after it
+        for bad in ["a = ?", "a = \"open", "a = \"\\"] {
+            let (document, diagnostics) = parse(&format!("{}\nb = 2\n", bad));
+            assert_eq!(diagnostics.len(), 1, "{}", bad);
+            assert_eq!(document.entries[0].key, "b", "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let array = Value::Array(vec![
+            Value::String("quote \" backslash \\ newline \n bell \u{7}".to_string()),
+            Value::Float(80.0),
+            Value::Float(0.1),
+            Value::Float(1e300),
+            Value::Integer(i64::MIN),
+            Value::Boolean(false),
+        ]);
+        let content = format!("a = {}", array);
+        assert_eq!(values(&content), [array]);
+        assert_eq!(Value::Float(80.0).to_string(), "80.0");
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(key("min-numeric"), "min-numeric");
+        assert_eq!(key("a b"), "\"a b\"");
+        assert_eq!(key(""), "\"\"");
+    }
+}
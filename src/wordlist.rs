@@ -0,0 +1,368 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// EFF short wordlist #1 (1,296 words, four dice per word)
+///
+/// Source: https://www.eff.org/dice
+const EFF_SHORT_WORDLIST: &str = include_str!("wordlists/eff_short_wordlist_1.txt");
+
+/// Lists smaller than this give less than ~10.3 bits per word
+const MIN_RECOMMENDED_WORDS: usize = 1296;
+
+/// A list of words for passphrase generation
+#[derive(Debug, Clone)]
+pub struct Wordlist {
+    words: Vec<String>,
+    /// Words that appeared more than once and were dropped while parsing
+    duplicates: Vec<String>,
+    /// Whether word positions match their diceware numbers (or no numbers were given)
+    dice_ordered: bool,
+}
+
+impl Wordlist {
+    /// The embedded EFF short wordlist
+    pub fn eff_short() -> Self {
+        Self::parse(EFF_SHORT_WORDLIST).expect("embedded wordlist is valid")
+    }
+
+    /// A built-in list by the name `--wordlist` accepts instead of a path,
+    /// or None if `name` is not one
+    ///
+    /// The EFF large list (7,776 words, five dice per word) is not bundled
+    /// yet, so `large` explains where to get it.
+    pub fn builtin(name: &str) -> Option<Result<Self, String>> {
+        match name {
+            "short" => Some(Ok(Self::eff_short())),
+            "large" => Some(Err(
+                "the EFF large wordlist is not bundled with this build; download eff_large_wordlist.txt from https://www.eff.org/dice and pass its path to --wordlist"
+                    .to_string(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Load a wordlist from a file
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or contains invalid entries
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Parse a wordlist from a string
+    ///
+    /// Accepts one word per line, either every one or none prefixed by a
+    /// diceware number (e.g. `11111\tabacus`). Blank lines and `#` comments
+    /// are skipped and duplicate words are dropped; an entry that is missing
+    /// its word or contains whitespace is an error.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut words = Vec::new();
+        let mut seen = HashSet::new();
+        let mut duplicates = Vec::new();
+        let mut dice_numbers = Vec::new();
+
+        let entries: Vec<(usize, Vec<&str>)> = content
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_number, line)| (line_number, line.split_whitespace().collect()))
+            .collect();
+
+        // The format is decided for the whole file, so a plain list may
+        // hold all-digit words such as "1234"
+        let numbered = !entries.is_empty()
+            && entries
+                .iter()
+                .all(|(_, tokens)| tokens.len() > 1 && Self::is_dice_number(tokens[0]));
+
+        for (line_number, mut tokens) in entries {
+            if numbered {
+                dice_numbers.push(tokens.remove(0));
+            } else if tokens.len() > 1 && Self::is_dice_number(tokens[0]) {
+                return Err(format!(
+                    "line {}: entry '{}' is numbered, but not every entry is",
+                    line_number,
+                    tokens.join(" ")
+                ));
+            }
+
+            let word = match tokens.as_slice() {
+                [] => return Err(format!("line {}: entry has no word", line_number)),
+                [word] => *word,
+                _ => {
+                    return Err(format!(
+                        "line {}: entry '{}' contains whitespace",
+                        line_number,
+                        tokens.join(" ")
+                    ));
+                }
+            };
+
+            if seen.insert(word) {
+                words.push(word.to_string());
+            } else {
+                duplicates.push(word.to_string());
+            }
+        }
+
+        // Numbered lists must be complete and in dice order to be used with dice
+        let dice_ordered = !numbered
+            || (dice_numbers.len() == words.len()
+                && dice_numbers
+                    .iter()
+                    .enumerate()
+                    .all(|(i, number)| Self::dice_index(number) == Some(i)));
+
+        Ok(Self {
+            words,
+            duplicates,
+            dice_ordered,
+        })
+    }
+
+    /// Whether a token looks like a diceware index (digits 1-6 only)
+    fn is_dice_number(token: &str) -> bool {
+        !token.is_empty() && token.bytes().all(|b| (b'1'..=b'6').contains(&b))
+    }
+
+    /// Convert a diceware number such as "3516" to a zero-based index
+    fn dice_index(number: &str) -> Option<usize> {
+        number.bytes().try_fold(0usize, |acc, b| {
+            if (b'1'..=b'6').contains(&b) {
+                acc.checked_mul(6)?.checked_add((b - b'1') as usize)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Number of d6 rolls that select one word
+    ///
+    /// Returns None unless the list size is a power of six.
+    pub fn dice_per_word(&self) -> Option<usize> {
+        let mut size = 1;
+        let mut dice = 0;
+        while size < self.words.len() {
+            size *= 6;
+            dice += 1;
+        }
+        (size == self.words.len() && dice > 0).then_some(dice)
+    }
+
+    /// Check that words can be selected with dice, returning the dice per word
+    pub fn check_dice(&self) -> Result<usize, String> {
+        let dice = self.dice_per_word().ok_or_else(|| {
+            format!(
+                "wordlist has {} words; dice input needs a power of six (e.g. 1296 or 7776)",
+                self.words.len()
+            )
+        })?;
+        if !self.dice_ordered {
+            return Err("wordlist numbering does not match dice order".to_string());
+        }
+        Ok(dice)
+    }
+
+    /// Map one word's worth of d6 rolls (e.g. "3 5 1 6 2") to a word index
+    ///
+    /// # Errors
+    /// Returns an error if the list cannot be used with dice, or if the
+    /// input has the wrong number of rolls or a roll outside 1-6
+    pub fn index_from_rolls(&self, input: &str) -> Result<usize, String> {
+        let dice = self.check_dice()?;
+
+        let rolls: String = input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ',')
+            .collect();
+
+        if let Some(bad) = rolls.chars().find(|c| !('1'..='6').contains(c)) {
+            return Err(format!("invalid roll '{}': each roll must be 1-6", bad));
+        }
+        if rolls.len() != dice {
+            return Err(format!("expected {} rolls, got {}", dice, rolls.len()));
+        }
+
+        Self::dice_index(&rolls).ok_or_else(|| "invalid dice rolls".to_string())
+    }
+
+    /// Number of words in the list
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Whether the list contains no words
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Get a word by index
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.words.get(index).map(|w| w.as_str())
+    }
+
+    /// Entropy contributed by one uniformly chosen word, in bits
+    pub fn entropy_per_word(&self) -> f64 {
+        (self.words.len() as f64).log2()
+    }
+
+    /// Find a word that is a prefix of another word, if any
+    ///
+    /// Without a separator, such pairs make passphrases ambiguous and
+    /// reduce the effective entropy.
+    pub fn prefix_pair(&self) -> Option<(&str, &str)> {
+        let mut sorted: Vec<&str> = self.words.iter().map(|w| w.as_str()).collect();
+        sorted.sort_unstable();
+
+        // In sorted order, a word is immediately followed by its extensions
+        sorted
+            .windows(2)
+            .find(|pair| pair[1].starts_with(pair[0]))
+            .map(|pair| (pair[0], pair[1]))
+    }
+
+    /// Human-readable warnings about the quality of the list
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if !self.duplicates.is_empty() {
+            warnings.push(format!(
+                "removed {} duplicate entries (e.g. '{}')",
+                self.duplicates.len(),
+                self.duplicates[0]
+            ));
+        }
+        if self.words.len() < MIN_RECOMMENDED_WORDS {
+            warnings.push(format!(
+                "only {} words ({:.2} bits per word); at least {} are recommended",
+                self.words.len(),
+                self.entropy_per_word(),
+                MIN_RECOMMENDED_WORDS
+            ));
+        }
+        if let Some((prefix, word)) = self.prefix_pair() {
+            warnings.push(format!(
+                "list is not prefix-free ('{}' is a prefix of '{}'); avoid an empty separator",
+                prefix, word
+            ));
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eff_short_wordlist() {
+        let list = Wordlist::eff_short();
+        assert_eq!(list.len(), 1296);
+        assert_eq!(list.get(0), Some("acid"));
+        assert_eq!(list.get(1295), Some("zoom"));
+        assert!(list.duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_builtin() {
+        assert_eq!(Wordlist::builtin("short").unwrap().unwrap().len(), 1296);
+        assert!(Wordlist::builtin("large").unwrap().is_err());
+        assert!(Wordlist::builtin("words.txt").is_none());
+    }
+
+    #[test]
+    fn test_parse_plain_and_numbered() {
+        let list = Wordlist::parse("11111\tabacus\n11112 abdomen\n\n11113 abide\n").unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.get(0), Some("abacus"));
+        assert_eq!(list.get(1), Some("abdomen"));
+        assert_eq!(list.get(2), Some("abide"));
+
+        let list = Wordlist::parse("abacus\nabdomen\nabide\n").unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.get(2), Some("abide"));
+    }
+
+    #[test]
+    fn test_parse_all_digit_plain_words() {
+        let list = Wordlist::parse("1234\n66\napple\n").unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.get(0), Some("1234"));
+        assert_eq!(list.get(1), Some("66"));
+        assert!(list.check_dice().is_err());
+
+        let list = Wordlist::parse("11111\n").unwrap();
+        assert_eq!(list.get(0), Some("11111"));
+    }
+
+    #[test]
+    fn test_parse_removes_duplicates() {
+        let list = Wordlist::parse("apple\nbanana\napple\n").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.duplicates, ["apple".to_string()]);
+        assert!(list.warnings().iter().any(|w| w.contains("duplicate")));
+    }
+
+    #[test]
+    fn test_parse_rejects_whitespace_and_empty_entries() {
+        assert!(Wordlist::parse("apple pie\n").is_err());
+        assert!(Wordlist::parse("11111 apple pie\n").is_err());
+        assert!(Wordlist::parse("11111 apple\n11112\n").is_err());
+        assert!(Wordlist::parse("11111 apple\nbanana\n").is_err());
+    }
+
+    #[test]
+    fn test_prefix_detection() {
+        let list = Wordlist::parse("car\ncarpet\ndog\n").unwrap();
+        assert_eq!(list.prefix_pair(), Some(("car", "carpet")));
+
+        let list = Wordlist::parse("cat\ndog\nemu\n").unwrap();
+        assert_eq!(list.prefix_pair(), None);
+    }
+
+    #[test]
+    fn test_index_from_rolls() {
+        let list = Wordlist::eff_short();
+        assert_eq!(list.dice_per_word(), Some(4));
+        assert_eq!(list.index_from_rolls("1111"), Ok(0));
+        assert_eq!(list.index_from_rolls("1 1 1 2"), Ok(1));
+        assert_eq!(list.index_from_rolls("6666"), Ok(1295));
+        assert_eq!(list.get(list.index_from_rolls("3516").unwrap()), Some("jaws"));
+
+        assert!(list.index_from_rolls("111").is_err());
+        assert!(list.index_from_rolls("11111").is_err());
+        assert!(list.index_from_rolls("1171").is_err());
+        assert!(list.index_from_rolls("1a11").is_err());
+    }
+
+    #[test]
+    fn test_dice_requires_power_of_six() {
+        let list = Wordlist::parse("apple\nbanana\ncherry\n").unwrap();
+        assert_eq!(list.dice_per_word(), None);
+        assert!(list.index_from_rolls("1").is_err());
+
+        let list = Wordlist::parse("1 a\n2 b\n3 c\n4 d\n5 e\n6 f\n").unwrap();
+        assert_eq!(list.dice_per_word(), Some(1));
+        assert_eq!(list.index_from_rolls("4"), Ok(3));
+
+        // Numbers out of dice order cannot be mapped from rolls
+        let list = Wordlist::parse("2 a\n1 b\n3 c\n4 d\n5 e\n6 f\n").unwrap();
+        assert!(list.index_from_rolls("1").is_err());
+    }
+
+    #[test]
+    fn test_entropy_per_word() {
+        let list = Wordlist::eff_short();
+        assert!((list.entropy_per_word() - 1296f64.log2()).abs() < 1e-9);
+    }
+}